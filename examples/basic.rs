@@ -1,10 +1,14 @@
 use egui_glow::glow;
-use fltk::{*, prelude::*};
+use fltk::{prelude::*, *};
 use std::rc::Rc;
-use std::{cell::RefCell, time::Instant};
+use std::{
+    cell::{Cell, RefCell},
+    time::Instant,
+};
 const SCREEN_WIDTH: u32 = 800;
 const SCREEN_HEIGHT: u32 = 600;
 
+// Wires the backend by hand, `fltk_egui::run_native` does all of this for you.
 fn main() {
     let fltk_app = app::App::default();
     let mut win = window::GlWindow::new(100, 100, SCREEN_WIDTH as _, SCREEN_HEIGHT as _, None)
@@ -16,26 +20,24 @@ fn main() {
     win.make_current();
 
     // Init backend
-    let (mut painter, mut egui_state) = fltk_egui::init(&mut win);
+    let (painter, mut egui_state) = fltk_egui::init(&mut win);
     // Set visual scale or egui display scaling
     egui_state.set_visual_scale(1.5);
+    let painter = Rc::new(RefCell::new(painter));
     let state = Rc::from(RefCell::from(egui_state));
+    let egui_ctx = egui::Context::default();
+    // Redraws the window when egui asks for a repaint.
+    let scheduler = fltk_egui::RepaintScheduler::new(&egui_ctx, &win);
 
     win.handle({
         let state = state.clone();
         move |win, ev| match ev {
-            enums::Event::Push
-            | enums::Event::Released
-            | enums::Event::KeyDown
-            | enums::Event::KeyUp
-            | enums::Event::MouseWheel
-            | enums::Event::Resize
-            | enums::Event::Move
-            | enums::Event::Drag => {
+            ev if fltk_egui::is_fused_event(ev) => {
                 // Using "if let ..." for safety.
                 if let Ok(mut state) = state.try_borrow_mut() {
-                    state.fuse_input(win, ev);
-                    true
+                    let consumed = state.fuse_input(win, ev);
+                    win.redraw();
+                    consumed
                 } else {
                     false
                 }
@@ -44,64 +46,69 @@ fn main() {
         }
     });
 
-    let egui_ctx = egui::Context::default();
-    let start_time = Instant::now();
-    let mut quit = false;
-    let mut age: i32 = 17;
-    let mut name: String = "".to_string();
-
-    while fltk_app.wait() {
-        // Clear the screen to dark red
-        let gl = painter.gl().as_ref();
-        draw_background(gl);
+    let quit = Rc::new(Cell::new(false));
+    win.draw({
+        let painter = painter.clone();
+        let quit = quit.clone();
+        let start_time = Instant::now();
+        let mut age: i32 = 17;
+        let mut name: String = "".to_string();
+        move |win| {
+            let mut state = state.borrow_mut();
+            let mut painter = painter.borrow_mut();
+            // Clear the screen to dark red
+            draw_background(painter.gl().as_ref());
 
-        let mut state = state.borrow_mut();
-        state.input.time = Some(start_time.elapsed().as_secs_f64());
-        let egui_output = egui_ctx.run(state.take_input(), |ctx| {
-            egui::CentralPanel::default().show(&ctx, |ui| {
-                ui.heading("My egui Application");
-                ui.horizontal(|ui| {
-                    ui.label("Your name: ");
-                    ui.text_edit_singleline(&mut name);
+            state.input.time = Some(start_time.elapsed().as_secs_f64());
+            let egui_output = egui_ctx.run(state.take_input(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.heading("My egui Application");
+                    ui.horizontal(|ui| {
+                        ui.label("Your name: ");
+                        ui.text_edit_singleline(&mut name);
+                    });
+                    ui.add(egui::Slider::new(&mut age, 0..=120).text("age"));
+                    if ui.button("Click each year").clicked() {
+                        age += 1;
+                    }
+                    ui.label(format!("Hello '{}', age {}", name, age));
+                    ui.separator();
+                    if ui
+                        .button("Quit?")
+                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                        .clicked()
+                    {
+                        quit.set(true);
+                    }
                 });
-                ui.add(egui::Slider::new(&mut age, 0..=120).text("age"));
-                if ui.button("Click each year").clicked() {
-                    age += 1;
-                }
-                ui.label(format!("Hello '{}', age {}", name, age));
-                ui.separator();
-                if ui
-                    .button("Quit?")
-                    .on_hover_cursor(egui::CursorIcon::PointingHand)
-                    .clicked()
-                {
-                    quit = true;
-                }
             });
-        });
-
-        if egui_ctx.has_requested_repaint() || state.window_resized() {
-            state.fuse_output(&mut win, egui_output.platform_output);
-            let meshes = egui_ctx.tessellate(egui_output.shapes, win.pixels_per_unit());
 
+            state.fuse_output(win, egui_output.platform_output);
+            // Tells fuse_input which events egui wants, and syncs the zoom.
+            state.update_from_context(&egui_ctx);
+            let meshes = egui_ctx.tessellate(egui_output.shapes, egui_output.pixels_per_point);
             painter.paint_and_update_textures(
                 state.canvas_size,
-                state.pixels_per_point(),
+                egui_output.pixels_per_point,
                 &meshes,
                 &egui_output.textures_delta,
             );
 
-            win.swap_buffers();
-            win.flush();
-            app::awake();
+            if let Some(viewport_output) = egui_output.viewport_output.get(&egui::ViewportId::ROOT)
+            {
+                scheduler.schedule(viewport_output.repaint_delay);
+            }
         }
+    });
 
-        if quit {
+    while fltk_app.wait() {
+        if quit.get() {
             break;
         }
     }
 
-    painter.destroy();
+    win.make_current();
+    painter.borrow_mut().destroy();
 }
 
 fn draw_background<GL: glow::HasContext>(gl: &GL) {
@@ -0,0 +1,61 @@
+use std::{cell::RefCell, rc::Rc};
+
+use fltk::{enums::*, prelude::*, *};
+use fltk_egui::SharedContext;
+
+const SCREEN_WIDTH: u32 = 800;
+const SCREEN_HEIGHT: u32 = 600;
+
+fn main() {
+    let fltk_app = app::App::default().with_scheme(app::Scheme::Gtk);
+    let mut main_win =
+        window::Window::new(100, 100, SCREEN_WIDTH as _, SCREEN_HEIGHT as _, None).center_screen();
+    let mut canvas = window::GlWindow::new(5, 5, main_win.w() - 260, main_win.h() - 10, None);
+    canvas.set_mode(Mode::Opengl3);
+    canvas.end();
+    let mut inspector = window::GlWindow::default()
+        .with_size(245, main_win.h() - 10)
+        .right_of(&canvas, 5);
+    inspector.set_mode(Mode::Opengl3);
+    inspector.end();
+    main_win.end();
+    main_win.make_resizable(true);
+    main_win.show();
+
+    // Both windows edit the same values.
+    let radius = Rc::new(RefCell::new(50.0));
+
+    let shared = SharedContext::new(&mut canvas, {
+        let radius = radius.clone();
+        move |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Canvas");
+                let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+                ui.painter()
+                    .circle_filled(rect.center(), *radius.borrow(), egui::Color32::RED);
+            });
+        }
+    });
+    shared.add_window(&mut inspector, {
+        let ctx = shared.context().clone();
+        move |inspector_ctx| {
+            egui::CentralPanel::default().show(inspector_ctx, |ui| {
+                ui.heading("Inspector");
+                if ui
+                    .add(egui::Slider::new(&mut *radius.borrow_mut(), 10.0..=200.0).text("radius"))
+                    .changed()
+                {
+                    ctx.request_repaint_of(egui::ViewportId::ROOT);
+                }
+            });
+        }
+    });
+
+    main_win.set_callback(move |win| {
+        // Before the GL context goes away with the canvas.
+        shared.destroy();
+        win.hide();
+    });
+
+    fltk_app.run().unwrap();
+}
@@ -1,8 +1,90 @@
-pub struct Clipboard {
+use std::borrow::Cow;
+
+use fltk::{app, prelude::WidgetExt, window::GlWindow};
+
+/// Where copied text goes to and pasted text comes from.
+pub trait ClipboardBackend {
+    /// Put `text` on the clipboard.
+    fn set_text(&mut self, text: String);
+
+    /// Ask for the clipboard text.
+    ///
+    /// Backends that can read synchronously return it right away. The others return `None`
+    /// and the text arrives later with an FLTK `Event::Paste` sent to `win`.
+    fn request_text(&mut self, win: &GlWindow) -> Option<String>;
+
+    /// Put `image` on the clipboard.
+    fn set_image(&mut self, _image: &egui::ColorImage) {
+        eprintln!("Copy error: this clipboard doesn't support images");
+    }
+
+    /// Ask for the clipboard image, same as [`Self::request_text`].
+    fn request_image(&mut self, _win: &GlWindow) -> Option<egui::ColorImage> {
+        None
+    }
+
+    /// Put `text` in the primary selection, pasted with a middle click on X11 and Wayland.
+    fn set_primary(&mut self, _text: String) {}
+
+    /// Ask for the primary selection text, same as [`Self::request_text`].
+    fn request_primary(&mut self, _win: &GlWindow) -> Option<String> {
+        None
+    }
+}
+
+/// Uses FLTK's own clipboard, pasted text arrives as an `Event::Paste`.
+#[derive(Default)]
+pub struct FltkClipboard;
+
+impl ClipboardBackend for FltkClipboard {
+    fn set_text(&mut self, text: String) {
+        app::copy(&text);
+    }
+
+    fn request_text(&mut self, win: &GlWindow) -> Option<String> {
+        defer_paste(win, app::paste_text);
+        None
+    }
+
+    fn request_image(&mut self, win: &GlWindow) -> Option<egui::ColorImage> {
+        defer_paste(win, app::paste_image);
+        None
+    }
+
+    fn set_primary(&mut self, text: String) {
+        app::copy2(&text);
+    }
+
+    fn request_primary(&mut self, win: &GlWindow) -> Option<String> {
+        defer_paste(win, app::paste_text2);
+        None
+    }
+}
+
+/// Asks FLTK for a paste into `win` once the current event is handled.
+///
+/// FLTK sends the `Event::Paste` right away when it has the data at hand, e.g. on Windows
+/// and macOS. Done from an event or draw callback, that would re-enter the callbacks of
+/// `win` while they still borrow the `EguiState`, and the pasted data would be lost.
+fn defer_paste(win: &GlWindow, paste: fn(&GlWindow)) {
+    let win = win.clone();
+    app::add_timeout3(0.0, move |_| {
+        if !win.was_deleted() {
+            paste(&win);
+        }
+    });
+}
+
+/// The arboard clipboard under its old name.
+#[deprecated(note = "renamed to ArboardClipboard")]
+pub type Clipboard = ArboardClipboard;
+
+/// Uses the arboard crate, which reads the clipboard synchronously.
+pub struct ArboardClipboard {
     arboard: Option<arboard::Clipboard>,
 }
 
-impl Default for Clipboard {
+impl Default for ArboardClipboard {
     fn default() -> Self {
         Self {
             arboard: init_arboard(),
@@ -10,7 +92,7 @@ impl Default for Clipboard {
     }
 }
 
-impl Clipboard {
+impl ArboardClipboard {
     pub fn get(&mut self) -> Option<String> {
         if let Some(clipboard) = &mut self.arboard {
             match clipboard.get_text() {
@@ -32,6 +114,96 @@ impl Clipboard {
             }
         }
     }
+
+    pub fn get_image(&mut self) -> Option<egui::ColorImage> {
+        if let Some(clipboard) = &mut self.arboard {
+            match clipboard.get_image() {
+                Ok(image) => Some(egui::ColorImage::from_rgba_unmultiplied(
+                    [image.width, image.height],
+                    &image.bytes,
+                )),
+                Err(err) => {
+                    eprintln!("Paste error: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn set_image(&mut self, image: &egui::ColorImage) {
+        if let Some(clipboard) = &mut self.arboard {
+            let bytes: Vec<u8> = image
+                .pixels
+                .iter()
+                .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+                .collect();
+            let image = arboard::ImageData {
+                width: image.width(),
+                height: image.height(),
+                bytes: Cow::Owned(bytes),
+            };
+            if let Err(err) = clipboard.set_image(image) {
+                eprintln!("Copy error: {}", err);
+            }
+        }
+    }
+}
+
+impl ClipboardBackend for ArboardClipboard {
+    fn set_text(&mut self, text: String) {
+        self.set(text);
+    }
+
+    fn request_text(&mut self, _win: &GlWindow) -> Option<String> {
+        self.get()
+    }
+
+    fn set_image(&mut self, image: &egui::ColorImage) {
+        ArboardClipboard::set_image(self, image);
+    }
+
+    fn request_image(&mut self, _win: &GlWindow) -> Option<egui::ColorImage> {
+        self.get_image()
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn set_primary(&mut self, text: String) {
+        use arboard::{LinuxClipboardKind, SetExtLinux};
+        if let Some(clipboard) = &mut self.arboard {
+            if let Err(err) = clipboard
+                .set()
+                .clipboard(LinuxClipboardKind::Primary)
+                .text(text)
+            {
+                eprintln!("Copy error: {}", err);
+            }
+        }
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn request_primary(&mut self, _win: &GlWindow) -> Option<String> {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        let clipboard = self.arboard.as_mut()?;
+        match clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+        {
+            Ok(text) => Some(text),
+            Err(err) => {
+                eprintln!("Paste error: {}", err);
+                None
+            }
+        }
+    }
 }
 
 fn init_arboard() -> Option<arboard::Clipboard> {
@@ -0,0 +1,220 @@
+use egui::{Color32, ColorImage, CursorIcon};
+use fltk::{enums, image::RgbImage, prelude::WindowExt, window::GlWindow};
+
+/// A cursor drawn from an image, for the icons FLTK has no stock cursor for.
+#[derive(Clone)]
+pub(crate) struct CursorImage {
+    image: ColorImage,
+    hotspot: [i32; 2],
+}
+
+impl CursorImage {
+    pub(crate) fn new(image: ColorImage, hotspot: [i32; 2]) -> Self {
+        Self { image, hotspot }
+    }
+
+    /// Set it as the cursor of `win`, returns false if FLTK rejected the image.
+    pub(crate) fn apply(&self, win: &mut GlWindow) -> bool {
+        let rgba: Vec<u8> = self
+            .image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+            .collect();
+        let [w, h] = self.image.size;
+        match RgbImage::new(&rgba, w as i32, h as i32, enums::ColorDepth::Rgba8) {
+            Ok(image) => {
+                win.set_cursor_image(image, self.hotspot[0], self.hotspot[1]);
+                true
+            }
+            Err(err) => {
+                eprintln!("Cursor error: {}", err);
+                false
+            }
+        }
+    }
+}
+
+/// FLTK's own cursor for `icon`, if it has one.
+pub(crate) fn stock_cursor(icon: CursorIcon) -> Option<enums::Cursor> {
+    Some(match icon {
+        CursorIcon::Default => enums::Cursor::Arrow,
+        CursorIcon::None => enums::Cursor::None,
+        CursorIcon::Help => enums::Cursor::Help,
+        CursorIcon::PointingHand => enums::Cursor::Hand,
+        CursorIcon::Progress | CursorIcon::Wait => enums::Cursor::Wait,
+        CursorIcon::Cell | CursorIcon::Crosshair => enums::Cursor::Cross,
+        CursorIcon::Text => enums::Cursor::Insert,
+        CursorIcon::Move | CursorIcon::AllScroll | CursorIcon::Grabbing => enums::Cursor::Move,
+        CursorIcon::Grab => enums::Cursor::Hand,
+        CursorIcon::ResizeHorizontal | CursorIcon::ResizeColumn => enums::Cursor::WE,
+        CursorIcon::ResizeVertical | CursorIcon::ResizeRow => enums::Cursor::NS,
+        CursorIcon::ResizeNeSw => enums::Cursor::NESW,
+        CursorIcon::ResizeNwSe => enums::Cursor::NWSE,
+        CursorIcon::ResizeEast => enums::Cursor::E,
+        CursorIcon::ResizeSouthEast => enums::Cursor::SE,
+        CursorIcon::ResizeSouth => enums::Cursor::S,
+        CursorIcon::ResizeSouthWest => enums::Cursor::SW,
+        CursorIcon::ResizeWest => enums::Cursor::W,
+        CursorIcon::ResizeNorthWest => enums::Cursor::NW,
+        CursorIcon::ResizeNorth => enums::Cursor::N,
+        CursorIcon::ResizeNorthEast => enums::Cursor::NE,
+        _ => return None,
+    })
+}
+
+/// The cursor image bundled for `icon`, for the icons without a stock cursor.
+pub(crate) fn bundled_cursor(icon: CursorIcon) -> Option<CursorImage> {
+    let (art, hotspot) = match icon {
+        CursorIcon::ContextMenu => (with_badge(&MENU_BADGE), [0, 0]),
+        CursorIcon::Alias => (with_badge(&ALIAS_BADGE), [0, 0]),
+        CursorIcon::Copy => (with_badge(&COPY_BADGE), [0, 0]),
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => (NOT_ALLOWED.map(String::from), [7, 7]),
+        CursorIcon::VerticalText => (VERTICAL_TEXT.map(String::from), [7, 7]),
+        CursorIcon::ZoomIn => (MAGNIFIER.map(|row| row.replace('+', "#")), [5, 5]),
+        CursorIcon::ZoomOut => (MAGNIFIER.map(|row| row.replace('+', ".")), [5, 5]),
+        _ => return None,
+    };
+    Some(CursorImage::new(from_art(&art), hotspot))
+}
+
+/// `#` is black, `.` white, `r` red and anything else transparent.
+fn from_art(art: &[String; 16]) -> ColorImage {
+    let pixels = art
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|c| match c {
+            '#' => Color32::BLACK,
+            '.' => Color32::WHITE,
+            'r' => Color32::from_rgb(0xd0, 0x20, 0x20),
+            _ => Color32::TRANSPARENT,
+        })
+        .collect();
+    ColorImage {
+        size: [16, 16],
+        pixels,
+    }
+}
+
+/// The arrow with a badge drawn over its bottom right corner.
+fn with_badge(badge: &[&str; 7]) -> [String; 16] {
+    let mut art = ARROW.map(String::from);
+    for (row, badge_row) in art[9..].iter_mut().zip(badge) {
+        row.replace_range(9.., badge_row);
+    }
+    art
+}
+
+#[rustfmt::skip]
+const ARROW: [&str; 16] = [
+    "#               ",
+    "##              ",
+    "#.#             ",
+    "#..#            ",
+    "#...#           ",
+    "#....#          ",
+    "#.....#         ",
+    "#......#        ",
+    "#.......#       ",
+    "#....#####      ",
+    "#..#..#         ",
+    "#.# #..#        ",
+    "##  #..#        ",
+    "#    #..#       ",
+    "     #..#       ",
+    "      ##        ",
+];
+
+#[rustfmt::skip]
+const MENU_BADGE: [&str; 7] = [
+    "#######",
+    "#.....#",
+    "#.###.#",
+    "#.....#",
+    "#.###.#",
+    "#.....#",
+    "#######",
+];
+
+#[rustfmt::skip]
+const ALIAS_BADGE: [&str; 7] = [
+    "#######",
+    "#.....#",
+    "#..##.#",
+    "#...#.#",
+    "#..#..#",
+    "#.#...#",
+    "#######",
+];
+
+#[rustfmt::skip]
+const COPY_BADGE: [&str; 7] = [
+    "#######",
+    "#.....#",
+    "#..#..#",
+    "#.###.#",
+    "#..#..#",
+    "#.....#",
+    "#######",
+];
+
+#[rustfmt::skip]
+const NOT_ALLOWED: [&str; 16] = [
+    "     ......     ",
+    "   ..rrrrrr..   ",
+    "  .rrrrrrrrrr.  ",
+    " .rrr......rrr. ",
+    " .rrrr.    .rr. ",
+    ".rr.rrr.    .rr.",
+    ".rr..rrr.   .rr.",
+    ".rr. .rrr.  .rr.",
+    ".rr.  .rrr. .rr.",
+    ".rr.   .rrr..rr.",
+    ".rr.    .rrr.rr.",
+    " .rr.    .rrrr. ",
+    " .rrr......rrr. ",
+    "  .rrrrrrrrrr.  ",
+    "   ..rrrrrr..   ",
+    "     ......     ",
+];
+
+#[rustfmt::skip]
+const VERTICAL_TEXT: [&str; 16] = [
+    "                ",
+    "                ",
+    "                ",
+    "                ",
+    "                ",
+    " .            . ",
+    ".#.          .#.",
+    ".##############.",
+    ".#.          .#.",
+    " .            . ",
+    "                ",
+    "                ",
+    "                ",
+    "                ",
+    "                ",
+    "                ",
+];
+
+/// `+` marks the vertical bar of the plus sign.
+#[rustfmt::skip]
+const MAGNIFIER: [&str; 16] = [
+    "   #####        ",
+    "  #.....#       ",
+    " #.......#      ",
+    "#....+....#     ",
+    "#....+....#     ",
+    "#..#####..#     ",
+    "#....+....#     ",
+    "#....+....#     ",
+    " #.......#      ",
+    "  #.....##      ",
+    "   ######.#     ",
+    "         #.#    ",
+    "          #.#   ",
+    "           #.#  ",
+    "            #.# ",
+    "             ## ",
+];
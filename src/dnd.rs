@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+/// Parses the text FLTK pastes after a drop into the dropped files.
+///
+/// On Linux this is a `text/uri-list` (`file://` URIs, percent-encoded, one per line),
+/// on Windows and macOS it is one plain path per line. FLTK doesn't say what the files
+/// are, so the mime type is guessed from the extension.
+pub(crate) fn parse_dropped_files(text: &str) -> Vec<egui::DroppedFile> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let path = PathBuf::from(uri_to_path(line));
+            egui::DroppedFile {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                mime: guess_mime(&path).to_owned(),
+                last_modified: path.metadata().and_then(|m| m.modified()).ok(),
+                path: Some(path),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn guess_mime(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "htm" | "html" => "text/html",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        // egui leaves it empty when unknown.
+        _ => "",
+    }
+}
+
+fn uri_to_path(line: &str) -> String {
+    match line.strip_prefix("file://") {
+        // Skip the (usually empty or "localhost") host part.
+        Some(rest) => percent_decode(&rest[rest.find('/').unwrap_or(0)..]),
+        None => line.to_owned(),
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(text: &str) -> Vec<PathBuf> {
+        parse_dropped_files(text)
+            .into_iter()
+            .map(|file| file.path.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn file_uris() {
+        assert_eq!(paths("file:///tmp/a.txt"), [PathBuf::from("/tmp/a.txt")]);
+        assert_eq!(
+            paths("file://localhost/tmp/a.txt"),
+            [PathBuf::from("/tmp/a.txt")]
+        );
+    }
+
+    #[test]
+    fn percent_encoded_uris() {
+        assert_eq!(
+            paths("file:///tmp/my%20file.txt"),
+            [PathBuf::from("/tmp/my file.txt")]
+        );
+        assert_eq!(
+            paths("file:///tmp/caf%C3%A9/%E2%9C%93.png"),
+            [PathBuf::from("/tmp/café/✓.png")]
+        );
+        // Not an escape, kept as is.
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn uri_lists() {
+        let files = parse_dropped_files("# comment\r\nfile:///a/b.png\r\nfile:///c/d.PDF\r\n\r\n");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, Some(PathBuf::from("/a/b.png")));
+        assert_eq!(files[0].name, "b.png");
+        assert_eq!(files[0].mime, "image/png");
+        assert_eq!(files[1].path, Some(PathBuf::from("/c/d.PDF")));
+        assert_eq!(files[1].name, "d.PDF");
+        assert_eq!(files[1].mime, "application/pdf");
+    }
+
+    #[test]
+    fn plain_paths() {
+        assert_eq!(
+            paths("/tmp/a b.txt\n/tmp/c%20d"),
+            [PathBuf::from("/tmp/a b.txt"), PathBuf::from("/tmp/c%20d")]
+        );
+        let files = parse_dropped_files("/tmp/Makefile");
+        assert_eq!(files[0].name, "Makefile");
+        assert_eq!(files[0].mime, "");
+    }
+}
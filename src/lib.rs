@@ -1,30 +1,60 @@
 #![doc = include_str!("../README.md")]
 #![warn(clippy::all)]
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 
-use egui::{pos2, vec2, CursorIcon, Event, Key, Modifiers, MouseWheelUnit, Pos2, RawInput, Rect};
+use egui::{
+    pos2, vec2, CursorIcon, Event, ImeEvent, Key, Modifiers, MouseWheelUnit, Pos2, RawInput, Rect,
+    Vec2,
+};
 use egui_glow::{glow, Painter};
-use egui_image::RetainedEguiImage;
+pub use egui_image::RetainedEguiImage;
 use fltk::{
     app, enums,
     prelude::{FltkError, ImageExt, WidgetExt, WindowExt},
     window::GlWindow,
 };
 
-mod clipboard;
+pub mod clipboard;
+mod cursor;
+mod dnd;
 mod egui_image;
-use clipboard::Clipboard;
+mod multi_viewport;
+mod repaint;
+mod runner;
+mod shared;
+mod storage;
+/// Pen and finger samples for [`EguiState::fuse_touch`].
+///
+/// Only their translation to egui is provided: the FLTK bundled with fltk-rs 1.5 has no
+/// pen API (`Fl::Pen`) yet, so nothing in this crate produces these samples.
+pub mod touch;
+mod viewport;
+mod widget;
+use clipboard::{ClipboardBackend, FltkClipboard};
+use cursor::CursorImage;
+pub use repaint::RepaintScheduler;
+pub use runner::{run_native, App, Frame, NativeOptions};
+pub use shared::SharedContext;
+pub use storage::{FileStorage, Storage};
+use touch::TouchPoint;
+pub use widget::EguiWidget;
+
+const DEFAULT_ZOOM_FACTOR: f32 = 8.0;
+/// Seconds for smooth scrolling to send about two thirds of a wheel delta.
+const SCROLL_TIME_CONSTANT: f32 = 0.06;
 
 /// Construct the backend.
 pub fn init(win: &mut GlWindow) -> (Painter, EguiState) {
-    app::set_screen_scale(win.screen_num(), 1.);
-    app::keyboard_screen_scaling(false);
     let gl = unsafe { glow::Context::from_loader_function(|s| win.get_proc_address(s) as _) };
     let painter = Painter::new(Arc::from(gl), "", None, false)
         .unwrap_or_else(|error| panic!("some OpenGL error occurred {}\n", error));
     let max_texture_side = painter.max_texture_side();
-    (painter, EguiState::new(&win, max_texture_side))
+    (painter, EguiState::new(win, max_texture_side))
 }
 
 /// Frame time for FPS.
@@ -34,14 +64,17 @@ pub fn get_frame_time(start_time: Instant) -> f32 {
 
 /// Casting slice to another type of slice
 pub fn cast_slice<T, D>(s: &[T]) -> &[D] {
-    unsafe {
-        std::slice::from_raw_parts(s.as_ptr() as *const D, s.len() * std::mem::size_of::<T>())
-    }
+    unsafe { std::slice::from_raw_parts(s.as_ptr() as *const D, std::mem::size_of_val(s)) }
 }
 
 /// The default cursor
 pub struct FusedCursor {
+    /// The last FLTK stock cursor set.
     pub cursor_icon: fltk::enums::Cursor,
+    /// The egui cursor shown, `None` when it must be set again.
+    current: Option<CursorIcon>,
+    /// Cursor images registered with [`FusedCursor::set_image`], `CursorIcon` isn't `Hash`.
+    images: Vec<(CursorIcon, CursorImage)>,
 }
 
 const ARROW: enums::Cursor = enums::Cursor::Arrow;
@@ -49,7 +82,28 @@ const ARROW: enums::Cursor = enums::Cursor::Arrow;
 impl FusedCursor {
     /// Construct a new cursor
     pub fn new() -> Self {
-        Self { cursor_icon: ARROW }
+        Self {
+            cursor_icon: ARROW,
+            current: Some(CursorIcon::Default),
+            images: Vec::new(),
+        }
+    }
+
+    /// Show `image` for `icon` instead of the stock or bundled cursor,
+    /// `hotspot` is the pixel of the image at the pointer position.
+    pub fn set_image(&mut self, icon: CursorIcon, image: egui::ColorImage, hotspot: [i32; 2]) {
+        let image = CursorImage::new(image, hotspot);
+        match self
+            .images
+            .iter_mut()
+            .find(|(registered, _)| *registered == icon)
+        {
+            Some((_, registered)) => *registered = image,
+            None => self.images.push((icon, image)),
+        }
+        if self.current == Some(icon) {
+            self.current = None;
+        }
     }
 }
 
@@ -61,61 +115,145 @@ impl Default for FusedCursor {
 
 /// Shuttles FLTK's input and events to Egui
 pub struct EguiState {
+    /// Size of the GL window in pixels.
     pub canvas_size: [u32; 2],
-    pub clipboard: Clipboard,
+    /// Defaults to FLTK's clipboard, see [`EguiState::set_clipboard`].
+    pub clipboard: Box<dyn ClipboardBackend>,
     pub fuse_cursor: FusedCursor,
     /// Use state.input.take() use this fn instead (to avoid pixels per point miscalculation).
     pub input: RawInput,
-    _pixels_per_point: f32,
+    /// Pixels per FLTK unit, following FLTK's scaling of the window's screen.
+    native_pixels_per_point: f32,
+    /// egui's zoom factor, see [`EguiState::set_visual_scale`].
+    visual_scale: f32,
+    /// Zoom factor to hand to egui after the next frame.
+    zoom_requested: Option<f32>,
+    /// Move egui's zoom into FLTK's screen scale, so the other FLTK widgets zoom along.
+    /// Default is false.
+    pub scale_fltk_widgets: bool,
+    /// Whether egui zooms on Ctrl +/-/0 itself.
+    zoom_with_keyboard: bool,
     pub pointer_pos: Pos2,
-    /// default value is 12.0
+    /// Points scrolled per wheel notch, default value is 12.0
     pub scroll_factor: f32,
-    /// default value is 8.0
+    /// Percent zoomed per Ctrl+wheel notch, default value is 8.0
     pub zoom_factor: f32,
     /// Internal use case for fn window_resized()
     _window_resized: bool,
     pub max_texture_side: usize,
+    /// The marked (not yet committed) text of an ongoing IME composition.
+    ime_preedit: Option<String>,
+    /// Keys currently held down, to tell auto-repeat from new presses.
+    pressed_keys: HashSet<Key>,
+    /// Set on DndRelease, the next Paste event carries the dropped files.
+    dnd_pending: bool,
+    /// The last image pasted through [`EguiState::request_image_paste`].
+    pasted_image: Option<egui::ColorImage>,
+    /// Publish the text selected in egui's text edits to the primary selection and paste it
+    /// with a middle click on the focused text edit, as usual on Linux. Default is false.
+    pub primary_selection: bool,
+    /// Where the focused text edit was in the last frame.
+    focused_text_edit: Option<Rect>,
+    /// Spread wheel scrolling over the next frames instead of jumping. Default is false.
+    pub smooth_scrolling: bool,
+    /// Scroll delta not sent to egui yet when smooth_scrolling is on.
+    pending_scroll: Vec2,
+    /// When pending_scroll was last sent.
+    scroll_time: Instant,
+    /// Pen and finger contacts still down, by device and contact id.
+    touches: HashMap<(u64, u64), TouchPoint>,
+    /// Whether egui used the pointer or keyboard in the last frame.
+    wants_pointer_input: bool,
+    wants_keyboard_input: bool,
+    /// See [`EguiState::close_requested`].
+    close_requested: bool,
+    /// Set by `ViewportCommand::MinInnerSize` and `MaxInnerSize`.
+    min_inner_size: Vec2,
+    max_inner_size: Vec2,
+    /// The egui viewport shown in this window.
+    viewport_id: egui::ViewportId,
 }
 
 impl EguiState {
     /// Construct a new state
     pub fn new(win: &GlWindow, max_texture_side: usize) -> EguiState {
-        let ppu = win.pixels_per_unit();
-        let (width, height) = (win.width(), win.height());
-        let rect = vec2(width as f32, height as f32) / ppu;
-        let screen_rect = Rect::from_min_size(Pos2::new(0f32, 0f32), rect);
-        EguiState {
-            canvas_size: [width as u32, height as u32],
-            clipboard: Clipboard::default(),
+        Self::with_canvas(
+            [win.pixel_w() as u32, win.pixel_h() as u32],
+            win.pixels_per_unit(),
+            max_texture_side,
+        )
+    }
+
+    /// A state for a canvas of `canvas_size` pixels, with `native_pixels_per_point`
+    /// pixels per FLTK unit.
+    fn with_canvas(
+        canvas_size: [u32; 2],
+        native_pixels_per_point: f32,
+        max_texture_side: usize,
+    ) -> EguiState {
+        let mut state = EguiState {
+            canvas_size,
+            clipboard: Box::new(FltkClipboard),
             fuse_cursor: FusedCursor::new(),
             input: egui::RawInput {
-                screen_rect: Some(screen_rect),
-                // pixels_per_point: Some(ppu),
                 max_texture_side: Some(max_texture_side),
                 ..Default::default()
             },
             max_texture_side,
-            _pixels_per_point: ppu,
+            native_pixels_per_point,
+            visual_scale: 1.0,
+            zoom_requested: None,
+            scale_fltk_widgets: false,
+            zoom_with_keyboard: true,
             pointer_pos: Pos2::new(0f32, 0f32),
             scroll_factor: 12.0,
-            zoom_factor: 8.0,
+            zoom_factor: DEFAULT_ZOOM_FACTOR,
             _window_resized: false,
-        }
+            ime_preedit: None,
+            pressed_keys: HashSet::new(),
+            dnd_pending: false,
+            pasted_image: None,
+            primary_selection: false,
+            focused_text_edit: None,
+            smooth_scrolling: false,
+            pending_scroll: Vec2::ZERO,
+            scroll_time: Instant::now(),
+            touches: HashMap::new(),
+            wants_pointer_input: true,
+            wants_keyboard_input: true,
+            close_requested: false,
+            min_inner_size: Vec2::ZERO,
+            max_inner_size: Vec2::INFINITY,
+            viewport_id: egui::ViewportId::ROOT,
+        };
+        state.update_screen_rect();
+        state
     }
 
     pub fn take_input(&mut self) -> egui::RawInput {
         self.input.max_texture_side = Some(self.max_texture_side);
-        // let pixels_per_point = self.input.pixels_per_point;
-        let take = self.input.take();
-        // self.input.pixels_per_point = pixels_per_point;
-        // if let Some(ppp) = pixels_per_point {
-        //     self._pixels_per_point = ppp;
-        // }
-        take
+        self.fuse_pending_scroll();
+        self.input.viewport_id = self.viewport_id;
+        // egui multiplies this by its zoom factor, the visual scale.
+        self.viewport_info().native_pixels_per_point = Some(self.native_pixels_per_point);
+        let input = self.input.take();
+        // The viewport info is kept for the next frames, its events aren't.
+        self.viewport_info().events.clear();
+        input
     }
 
+    /// The pixels per point egui is run with: FLTK's scale for the window's screen
+    /// times the visual scale.
+    ///
+    /// Tessellate and paint with `FullOutput::pixels_per_point`, which is the same
+    /// except in the frame the zoom changes.
     pub fn pixels_per_point(&self) -> f32 {
-        self._pixels_per_point
+        self.native_pixels_per_point * self.visual_scale
+    }
+
+    /// See [`EguiState::set_visual_scale`].
+    pub fn visual_scale(&self) -> f32 {
+        self.visual_scale
     }
 
     /// Check if current window being resized.
@@ -125,46 +263,377 @@ impl EguiState {
         tmp
     }
 
-    /// Conveniece method bundling the necessary components for input/event handling
-    pub fn fuse_input(&mut self, win: &mut GlWindow, event: enums::Event) {
+    /// Conveniece method bundling the necessary components for input/event handling.
+    ///
+    /// Returns whether egui consumed the event, as of the last [`EguiState::update_from_context`].
+    /// Return it from the `handle` callback so FLTK gets the clicks and keys egui doesn't use.
+    pub fn fuse_input(&mut self, win: &mut GlWindow, event: enums::Event) -> bool {
         input_to_egui(win, event, self);
+        match event {
+            enums::Event::Push
+            | enums::Event::Released
+            | enums::Event::Drag
+            | enums::Event::MouseWheel
+            | enums::Event::ZoomGesture => self.wants_pointer_input,
+            enums::Event::KeyDown | enums::Event::KeyUp => {
+                self.wants_keyboard_input || self.is_zoom_shortcut()
+            }
+            // FLTK only sends moves, drops and keys to windows accepting these.
+            enums::Event::Enter
+            | enums::Event::Move
+            | enums::Event::Leave
+            | enums::Event::DndEnter
+            | enums::Event::DndDrag
+            | enums::Event::DndLeave
+            | enums::Event::DndRelease
+            | enums::Event::Focus
+            | enums::Event::Unfocus
+            | enums::Event::Paste => true,
+            _ => false,
+        }
+    }
+
+    /// Remember which input egui wants and where its focused text edit is,
+    /// and sync the zoom factor. Call this after each frame.
+    pub fn update_from_context(&mut self, ctx: &egui::Context) {
+        // Once its frame ended, egui answers for another viewport.
+        if ctx.viewport_id() == self.viewport_id {
+            self.update_from_viewport(ctx);
+        }
+        self.zoom_with_keyboard = ctx.options(|options| options.zoom_with_keyboard);
+
+        if let Some(zoom) = self.zoom_requested.take() {
+            ctx.set_zoom_factor(zoom);
+            return;
+        }
+        let mut zoom = ctx.zoom_factor();
+        if self.scale_fltk_widgets && zoom != 1.0 {
+            // egui follows FLTK's scale through the native pixels per point.
+            for screen in 0..app::screen_count() {
+                app::set_screen_scale(screen, app::screen_scale(screen) * zoom);
+            }
+            ctx.set_zoom_factor(1.0);
+            zoom = 1.0;
+        }
+        if zoom != self.visual_scale {
+            self.visual_scale = zoom;
+            self.update_screen_rect();
+        }
+    }
+
+    /// Remember which input egui wants and where its focused text edit is, which egui
+    /// keeps per viewport. Windows of other viewports than the root call this at the end
+    /// of their `Context::run`, while egui still runs their viewport.
+    pub(crate) fn update_from_viewport(&mut self, ctx: &egui::Context) {
+        self.wants_pointer_input = ctx.wants_pointer_input();
+        self.wants_keyboard_input = ctx.wants_keyboard_input();
+        self.focused_text_edit = ctx
+            .memory(|memory| memory.focused())
+            .filter(|&id| egui::TextEdit::load_state(ctx, id).is_some())
+            .and_then(|id| ctx.read_response(id))
+            .map(|response| response.rect);
+    }
+
+    /// Whether the key pressed is one of egui's zoom shortcuts,
+    /// which FLTK would otherwise also use to scale its widgets.
+    fn is_zoom_shortcut(&self) -> bool {
+        self.zoom_with_keyboard
+            && self.input.modifiers.command
+            && matches!(
+                translate_virtual_key_code(app::event_key()),
+                Some(Key::Plus | Key::Equals | Key::Minus | Key::Num0)
+            )
     }
 
     /// Convenience method for outputting what egui emits each frame
     pub fn fuse_output(&mut self, win: &mut GlWindow, egui_output: egui::PlatformOutput) {
-        if !egui_output.copied_text.is_empty() {
-            self.clipboard.set(egui_output.copied_text);
-        }
+        self.fuse_copied_text(egui_output.copied_text);
+        self.fuse_text_selection(&egui_output.events);
         if win.damage() {
             win.clear_damage();
         }
+        self.fuse_ime_output(win, egui_output.ime);
         translate_cursor(win, &mut self.fuse_cursor, egui_output.cursor_icon);
     }
 
     /// Convenience method for outputting what egui emits each frame (borrow PlatformOutput)
     pub fn fuse_output_borrow(&mut self, win: &mut GlWindow, egui_output: &egui::PlatformOutput) {
-        if !egui_output.copied_text.is_empty() {
-            app::copy(&egui_output.copied_text);
-        }
+        self.fuse_copied_text(egui_output.copied_text.clone());
+        self.fuse_text_selection(&egui_output.events);
         if win.damage() {
             win.clear_damage();
         }
+        self.fuse_ime_output(win, egui_output.ime);
         translate_cursor(win, &mut self.fuse_cursor, egui_output.cursor_icon);
     }
 
+    fn fuse_copied_text(&mut self, text: String) {
+        if !text.is_empty() {
+            self.clipboard.set_text(text);
+        }
+    }
+
+    /// Publish the text selected in the focused text edit to the primary selection.
+    fn fuse_text_selection(&mut self, events: &[egui::output::OutputEvent]) {
+        if !self.primary_selection {
+            return;
+        }
+        for event in events {
+            let egui::output::OutputEvent::TextSelectionChanged(info) = event else {
+                continue;
+            };
+            let (Some(range), Some(text)) = (&info.text_selection, &info.current_text_value) else {
+                continue;
+            };
+            let start = *range.start().min(range.end());
+            let end = *range.start().max(range.end());
+            let selected: String = text.chars().skip(start).take(end - start).collect();
+            // Password fields only tell their mask.
+            if !selected.is_empty()
+                && selected
+                    .chars()
+                    .any(|c| c != egui::epaint::text::PASSWORD_REPLACEMENT_CHAR)
+            {
+                self.clipboard.set_primary(selected);
+            }
+        }
+    }
+
+    /// Whether smooth scrolling still has some delta to send, keep repainting until it's done.
+    pub fn is_scrolling(&self) -> bool {
+        self.pending_scroll != Vec2::ZERO
+    }
+
+    fn scroll(&mut self, delta: Vec2) {
+        if !self.smooth_scrolling {
+            self.input.events.push(Event::MouseWheel {
+                unit: MouseWheelUnit::Point,
+                delta,
+                modifiers: self.input.modifiers,
+            });
+            return;
+        }
+        if !self.is_scrolling() {
+            self.scroll_time = Instant::now();
+        }
+        self.pending_scroll += delta;
+    }
+
+    /// Send the part of the pending scroll due this frame, the rest decays exponentially.
+    fn fuse_pending_scroll(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.scroll_time).as_secs_f32();
+        self.scroll_time = now;
+        if !self.is_scrolling() {
+            return;
+        }
+        let mut delta = self.pending_scroll * (1. - (-dt / SCROLL_TIME_CONSTANT).exp());
+        if (self.pending_scroll - delta).length() < 0.5 {
+            delta = self.pending_scroll;
+        }
+        self.pending_scroll -= delta;
+        self.input.events.push(Event::MouseWheel {
+            unit: MouseWheelUnit::Point,
+            delta,
+            modifiers: self.input.modifiers,
+        });
+    }
+
+    fn zoom_per_notch(&self) -> f32 {
+        1. + self.zoom_factor / 100.
+    }
+
+    /// Show `image` for the egui cursor `icon`, see [`FusedCursor::set_image`].
+    pub fn set_cursor_image(
+        &mut self,
+        icon: CursorIcon,
+        image: egui::ColorImage,
+        hotspot: [i32; 2],
+    ) {
+        self.fuse_cursor.set_image(icon, image, hotspot);
+    }
+
+    /// Use another clipboard, e.g. [`clipboard::ArboardClipboard`]
+    pub fn set_clipboard(&mut self, clipboard: impl ClipboardBackend + 'static) {
+        self.clipboard = Box::new(clipboard);
+    }
+
+    /// Ask for the clipboard image, get it with [`EguiState::take_pasted_image`] once it arrived.
+    pub fn request_image_paste(&mut self, win: &GlWindow) {
+        if let Some(image) = self.clipboard.request_image(win) {
+            self.pasted_image = Some(image);
+        }
+    }
+
+    /// The image pasted since the last call, if any.
+    pub fn take_pasted_image(&mut self) -> Option<egui::ColorImage> {
+        self.pasted_image.take()
+    }
+
+    /// Put an image on the clipboard.
+    pub fn copy_image(&mut self, image: &egui::ColorImage) {
+        self.clipboard.set_image(image);
+    }
+
+    /// Place the IME candidate window at egui's text cursor,
+    /// and drop an unfinished composition once no text field has focus.
+    fn fuse_ime_output(&mut self, win: &GlWindow, ime: Option<egui::output::IMEOutput>) {
+        match ime {
+            Some(ime) => {
+                // FLTK places it in window units, at the baseline of the text.
+                let ppp = self.visual_scale;
+                let cursor = ime.cursor_rect;
+                let height = (cursor.height() * ppp) as i32;
+                fltk::draw::set_spot(
+                    enums::Font::Helvetica,
+                    height,
+                    (cursor.left() * ppp) as i32,
+                    (cursor.bottom() * ppp) as i32,
+                    (cursor.width() * ppp) as i32,
+                    height,
+                    win,
+                );
+            }
+            None => {
+                if self.ime_preedit.take().is_some() {
+                    app::compose_reset();
+                    fltk::draw::reset_spot();
+                    self.input.events.push(Event::Ime(ImeEvent::Disabled));
+                }
+            }
+        }
+    }
+
+    /// Translates the result of `app::compose()` into text or IME events.
+    ///
+    /// `del` is the number of bytes of previously composed text the new `text` replaces,
+    /// `marked_len` is FLTK's compose state: the number of bytes at the end of the composed
+    /// text that are still being composed. The text before them is final.
+    pub fn fuse_compose(&mut self, del: i32, text: &str, marked_len: i32) {
+        let composing = self.ime_preedit.is_some();
+        if !composing && marked_len <= 0 {
+            if !text.is_empty() && !text.chars().any(char::is_control) {
+                self.input.events.push(Event::Text(text.to_owned()));
+            }
+            return;
+        }
+        let mut composed = self.ime_preedit.take().unwrap_or_default();
+        let mut keep = composed.len().saturating_sub(del.max(0) as usize);
+        while !composed.is_char_boundary(keep) {
+            keep -= 1;
+        }
+        composed.truncate(keep);
+        composed.push_str(text);
+
+        let mut split = composed.len().saturating_sub(marked_len.max(0) as usize);
+        while !composed.is_char_boundary(split) {
+            split -= 1;
+        }
+        let marked = composed.split_off(split);
+        if !composing {
+            self.input.events.push(Event::Ime(ImeEvent::Enabled));
+        }
+        if !composed.is_empty() {
+            self.input.events.extend([
+                Event::Ime(ImeEvent::Preedit(String::new())),
+                Event::Ime(ImeEvent::Commit(composed)),
+            ]);
+        }
+        if marked.is_empty() {
+            self.input.events.push(Event::Ime(ImeEvent::Disabled));
+        } else {
+            self.input
+                .events
+                .push(Event::Ime(ImeEvent::Preedit(marked.clone())));
+            self.ime_preedit = Some(marked);
+        }
+    }
+
+    /// Sends a pen or touch sample to egui, see [`TouchPoint`].
+    ///
+    /// [`EguiState::fuse_input`] never calls this, FLTK's events carry no touch samples.
+    pub fn fuse_touch(&mut self, point: TouchPoint) {
+        let contact = (point.device_id.0, point.id);
+        match point.phase {
+            egui::TouchPhase::Start | egui::TouchPhase::Move => {
+                self.touches.insert(contact, point);
+            }
+            egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                self.touches.remove(&contact);
+            }
+        }
+        self.input.events.push(point.to_egui(self.visual_scale));
+    }
+
     /// Set visual scale, e.g: 0.8, 1.5, 2.0 .etc (default is 1.0)
+    ///
+    /// This is egui's zoom factor, which also changes with Ctrl +/-/0. It scales egui on top
+    /// of FLTK's screen scale and is passed on to egui by [`EguiState::update_from_context`].
+    /// Sizes that aren't finite and positive are ignored.
     pub fn set_visual_scale(&mut self, size: f32) {
-        // have to be setted the pixels_per_point of both the inner (input) and the state.
-        // self.input.pixels_per_point = Some(size);
-        self._pixels_per_point = size;
+        if !(size.is_finite() && size > 0.0) {
+            return;
+        }
+        self.visual_scale = size;
+        self.zoom_requested = Some(size);
+        self.update_screen_rect();
+    }
 
-        // resize rect with canvas.
+    /// Follow the window's size and FLTK's scale of the screen it is on.
+    ///
+    /// egui rebuilds its font atlas by itself when the pixels per point change.
+    pub(crate) fn fuse_window_scale(&mut self, win: &GlWindow) {
+        self.canvas_size = [win.pixel_w() as u32, win.pixel_h() as u32];
+        self.native_pixels_per_point = win.pixels_per_unit();
+        self.update_screen_rect();
+    }
+
+    /// Resize egui's screen rect with the canvas.
+    fn update_screen_rect(&mut self) {
         let canvas_size = self.canvas_size;
-        let rect = vec2(canvas_size[0] as f32, canvas_size[1] as f32) / size;
+        let rect = vec2(canvas_size[0] as f32, canvas_size[1] as f32) / self.pixels_per_point();
         self.input.screen_rect = Some(Rect::from_min_size(Default::default(), rect));
     }
 }
 
+/// The events [`EguiState::fuse_input`] turns into egui input.
+///
+/// Use it to forward only these from the `handle` callback of the window:
+/// ```rust,ignore
+/// win.handle(move |win, ev| {
+///     if fltk_egui::is_fused_event(ev) {
+///         state.borrow_mut().fuse_input(win, ev)
+///     } else {
+///         false
+///     }
+/// });
+/// ```
+pub fn is_fused_event(event: enums::Event) -> bool {
+    matches!(
+        event,
+        enums::Event::Push
+            | enums::Event::Released
+            | enums::Event::KeyDown
+            | enums::Event::KeyUp
+            | enums::Event::MouseWheel
+            | enums::Event::ZoomGesture
+            | enums::Event::Resize
+            | enums::Event::ScreenConfigChanged
+            | enums::Event::ZoomEvent
+            | enums::Event::Move
+            | enums::Event::Drag
+            | enums::Event::Enter
+            | enums::Event::Leave
+            | enums::Event::Focus
+            | enums::Event::Unfocus
+            | enums::Event::DndEnter
+            | enums::Event::DndDrag
+            | enums::Event::DndLeave
+            | enums::Event::DndRelease
+            | enums::Event::Paste
+    )
+}
+
 /// Handles input/events from FLTK
 pub fn input_to_egui(
     win: &mut GlWindow,
@@ -174,151 +643,197 @@ pub fn input_to_egui(
 ) {
     match event {
         enums::Event::Resize => {
-            state.canvas_size = [win.width() as u32, win.height() as u32];
-            state.set_visual_scale(state.pixels_per_point());
+            // Also sent when the window moves, maybe to a screen with another scale.
+            state.fuse_window_scale(win);
             state._window_resized = true;
         }
 
-        //MouseButonLeft pressed is the only one needed by egui
+        enums::Event::ScreenConfigChanged | enums::Event::ZoomEvent => {
+            // Screens were changed or FLTK's scale was changed (Ctrl +/- by default).
+            state.fuse_window_scale(win);
+        }
+
         enums::Event::Push => {
-            let mouse_btn = match app::event_mouse_button() {
-                app::MouseButton::Left => Some(egui::PointerButton::Primary),
-                app::MouseButton::Middle => Some(egui::PointerButton::Middle),
-                app::MouseButton::Right => Some(egui::PointerButton::Secondary),
-                _ => None,
-            };
+            state.pointer_pos = event_pos(win, state.visual_scale);
+            let mouse_btn = translate_mouse_button(app::event_button());
             if let Some(pressed) = mouse_btn {
                 state.input.events.push(egui::Event::PointerButton {
                     pos: state.pointer_pos,
                     button: pressed,
                     pressed: true,
                     modifiers: state.input.modifiers,
-                })
+                });
+                let on_text_edit = state
+                    .focused_text_edit
+                    .is_some_and(|rect| rect.contains(state.pointer_pos));
+                if pressed == egui::PointerButton::Middle && state.primary_selection && on_text_edit
+                {
+                    // Otherwise the text arrives with an Event::Paste.
+                    if let Some(text) = state.clipboard.request_primary(win) {
+                        state.input.events.push(Event::Paste(text));
+                    }
+                }
             }
         }
 
-        //MouseButonLeft pressed is the only one needed by egui
         enums::Event::Released => {
             // fix unreachable, we can use Option.
-            let mouse_btn = match app::event_mouse_button() {
-                app::MouseButton::Left => Some(egui::PointerButton::Primary),
-                app::MouseButton::Middle => Some(egui::PointerButton::Middle),
-                app::MouseButton::Right => Some(egui::PointerButton::Secondary),
-                _ => None,
-            };
+            let mouse_btn = translate_mouse_button(app::event_button());
             if let Some(released) = mouse_btn {
                 state.input.events.push(egui::Event::PointerButton {
                     pos: state.pointer_pos,
                     button: released,
                     pressed: false,
                     modifiers: state.input.modifiers,
-                })
+                });
             }
         }
 
-        enums::Event::Move | enums::Event::Drag => {
-            let ppp = state.pixels_per_point();
-            let (x, y) = app::event_coords();
-            state.pointer_pos = pos2(x as f32 / ppp, y as f32 / ppp);
+        enums::Event::Enter | enums::Event::Move | enums::Event::Drag => {
+            state.pointer_pos = event_pos(win, state.visual_scale);
             state
                 .input
                 .events
                 .push(egui::Event::PointerMoved(state.pointer_pos))
         }
 
+        enums::Event::Leave => state.input.events.push(Event::PointerGone),
+
+        enums::Event::Focus => {
+            state.input.focused = true;
+            state.input.events.push(Event::WindowFocused(true));
+        }
+
+        enums::Event::Unfocus => {
+            state.input.focused = false;
+            state.input.events.push(Event::WindowFocused(false));
+            // The key releases go to another window, don't leave keys or modifiers held.
+            state.input.modifiers = Modifiers::default();
+            for key in state.pressed_keys.drain() {
+                state.input.events.push(Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed: false,
+                    repeat: false,
+                    modifiers: state.input.modifiers,
+                });
+            }
+            let touches: Vec<TouchPoint> = state.touches.values().copied().collect();
+            for touch in touches {
+                state.fuse_touch(TouchPoint {
+                    phase: egui::TouchPhase::Cancel,
+                    ..touch
+                });
+            }
+        }
+
+        enums::Event::DndEnter | enums::Event::DndDrag => {
+            state.pointer_pos = event_pos(win, state.visual_scale);
+            state
+                .input
+                .events
+                .push(egui::Event::PointerMoved(state.pointer_pos));
+            if state.input.hovered_files.is_empty() {
+                // FLTK only hands over what is being dragged once it is dropped.
+                state.input.hovered_files.push(egui::HoveredFile::default());
+            }
+        }
+
+        enums::Event::DndLeave => {
+            state.input.hovered_files.clear();
+        }
+
+        enums::Event::DndRelease => {
+            state.input.hovered_files.clear();
+            // The dropped data arrives with the Paste event that follows.
+            state.dnd_pending = true;
+        }
+
+        enums::Event::Paste => {
+            if let Some(app::ClipboardEvent::Image(Some(image))) = app::event_clipboard() {
+                state.pasted_image = color_image_from_rgb(&image).ok();
+                return;
+            }
+            let text = app::event_text();
+            if std::mem::take(&mut state.dnd_pending) {
+                state
+                    .input
+                    .dropped_files
+                    .extend(dnd::parse_dropped_files(&text));
+            } else if !text.is_empty() {
+                state.input.events.push(Event::Paste(text));
+            }
+        }
+
         enums::Event::KeyUp => {
+            state.input.modifiers = translate_modifiers(app::event_state());
             if let Some(key) = translate_virtual_key_code(app::event_key()) {
-                let keymod = app::event_state();
-                state.input.modifiers = Modifiers {
-                    alt: (keymod & enums::EventState::Alt == enums::EventState::Alt),
-                    ctrl: (keymod & enums::EventState::Ctrl == enums::EventState::Ctrl),
-                    shift: (keymod & enums::EventState::Shift == enums::EventState::Shift),
-                    mac_cmd: keymod & enums::EventState::Meta == enums::EventState::Meta,
-
-                    //TOD: Test on both windows and mac
-                    command: (keymod & enums::EventState::Command == enums::EventState::Command),
-                };
-                if state.input.modifiers.command && key == Key::V {
-                    if let Some(value) = state.clipboard.get() {
-                        state.input.events.push(egui::Event::Text(value));
-                    }
-                }
+                state.pressed_keys.remove(&key);
+                state.input.events.push(Event::Key {
+                    key,
+                    physical_key: translate_virtual_key_code(app::event_original_key()),
+                    pressed: false,
+                    modifiers: state.input.modifiers,
+                    repeat: false,
+                });
             }
         }
 
         enums::Event::KeyDown => {
-            if let Some(c) = app::event_text().chars().next() {
-                if let Some(del) = app::compose() {
-                    state.input.events.push(Event::Text(c.to_string()));
-                    if del != 0 {
-                        app::compose_reset();
-                    }
-                }
+            if let Some(del) = app::compose() {
+                state.fuse_compose(del, &app::event_text(), app::compose_state());
             }
+            state.input.modifiers = translate_modifiers(app::event_state());
             if let Some(key) = translate_virtual_key_code(app::event_key()) {
-                let keymod = app::event_state();
-                state.input.modifiers = Modifiers {
-                    alt: (keymod & enums::EventState::Alt == enums::EventState::Alt),
-                    ctrl: (keymod & enums::EventState::Ctrl == enums::EventState::Ctrl),
-                    shift: (keymod & enums::EventState::Shift == enums::EventState::Shift),
-                    mac_cmd: keymod & enums::EventState::Meta == enums::EventState::Meta,
-
-                    //TOD: Test on both windows and mac
-                    command: (keymod & enums::EventState::Command == enums::EventState::Command),
-                };
-
+                // FLTK sends auto-repeated presses as KeyDown without a KeyUp in between.
+                let repeat = !state.pressed_keys.insert(key);
                 state.input.events.push(Event::Key {
                     key,
-                    physical_key: None,
+                    // FLTK doesn't expose scan codes, the untranslated key code is the closest.
+                    physical_key: translate_virtual_key_code(app::event_original_key()),
                     pressed: true,
                     modifiers: state.input.modifiers,
-                    repeat: false,
+                    repeat,
                 });
 
-                if state.input.modifiers.command && key == Key::C {
-                    // println!("copy event");
-                    state.input.events.push(Event::Copy)
-                } else if state.input.modifiers.command && key == Key::X {
-                    // println!("cut event");
-                    state.input.events.push(Event::Cut)
-                } else {
-                    state.input.events.push(Event::Key {
-                        key,
-                        physical_key: None,
-                        pressed: false,
-                        modifiers: state.input.modifiers,
-                        repeat: false,
-                    })
+                let command = state.input.modifiers.command;
+                if key == Key::Copy || (command && key == Key::C) {
+                    state.input.events.push(Event::Copy);
+                } else if key == Key::Cut || (command && key == Key::X) {
+                    state.input.events.push(Event::Cut);
+                } else if key == Key::Paste || (command && key == Key::V) {
+                    // Otherwise the text arrives with an Event::Paste.
+                    if let Some(text) = state.clipboard.request_text(win) {
+                        state.input.events.push(Event::Paste(text));
+                    }
                 }
             }
         }
 
         enums::Event::MouseWheel => {
-            let keymod = app::event_state();
-            state.input.modifiers = Modifiers {
-                alt: (keymod & enums::EventState::Alt == enums::EventState::Alt),
-                ctrl: (keymod & enums::EventState::Ctrl == enums::EventState::Ctrl),
-                shift: (keymod & enums::EventState::Shift == enums::EventState::Shift),
-                mac_cmd: keymod & enums::EventState::Meta == enums::EventState::Meta,
-
-                //TOD: Test on both windows and mac
-                command: (keymod & enums::EventState::Command == enums::EventState::Command),
-            };
-            let negx = match app::event_dx() {
-                app::MouseWheel::Right => 1.,
-                app::MouseWheel::Left => -1.,
-                _ => 0.,
-            };
-            let negy = match app::event_dy() {
-                app::MouseWheel::Up => -1.,
-                app::MouseWheel::Down => 1.,
-                _ => 0.,
-            };
-            state.input.events.push(Event::MouseWheel {
-                unit: MouseWheelUnit::Line,
-                delta: vec2(1. * negx, 1. * negy) ,
-                modifiers: state.input.modifiers,
-            });
+            state.input.modifiers = translate_modifiers(app::event_state());
+            // FLTK sends whole steps, positive to the right and down. Several notches
+            // can come at once, and precise touchpads send small steps more often.
+            let steps = vec2(app::event_dx_value() as f32, app::event_dy_value() as f32);
+            if state.input.modifiers.ctrl {
+                state
+                    .input
+                    .events
+                    .push(Event::Zoom(state.zoom_per_notch().powf(-steps.y)));
+            } else {
+                state.scroll(-steps * state.scroll_factor);
+            }
+        }
+
+        enums::Event::ZoomGesture => {
+            // FLTK reports the pinch magnification in thousandths,
+            // at the default zoom_factor a pinch zooms as much as the gesture.
+            let magnification = app::event_dy_value() as f32 / 1000.;
+            let notches = magnification * 100. / DEFAULT_ZOOM_FACTOR;
+            state
+                .input
+                .events
+                .push(Event::Zoom(state.zoom_per_notch().powf(notches)));
         }
         _ => {
             //dbg!(event);
@@ -326,6 +841,94 @@ pub fn input_to_egui(
     }
 }
 
+/// The pointer position in `win`, also when the event is delivered by a parent window,
+/// which `app::event_coords()` is relative to.
+///
+/// FLTK units are already scaled for the screen, only the visual scale is left to undo.
+fn event_pos(win: &GlWindow, visual_scale: f32) -> Pos2 {
+    let x = app::event_x_root() - win.x_root();
+    let y = app::event_y_root() - win.y_root();
+    pos2(x as f32 / visual_scale, y as f32 / visual_scale)
+}
+
+/// Translates an FLTK mouse button number, as returned by `app::event_button()`
+pub fn translate_mouse_button(button: i32) -> Option<egui::PointerButton> {
+    match button {
+        FL_LEFT_MOUSE => Some(egui::PointerButton::Primary),
+        FL_MIDDLE_MOUSE => Some(egui::PointerButton::Middle),
+        FL_RIGHT_MOUSE => Some(egui::PointerButton::Secondary),
+        FL_BACK_MOUSE => Some(egui::PointerButton::Extra1),
+        FL_FORWARD_MOUSE => Some(egui::PointerButton::Extra2),
+        _ => None,
+    }
+}
+
+/// Translates FLTK modifier state
+pub fn translate_modifiers(keymod: enums::EventState) -> Modifiers {
+    Modifiers {
+        alt: (keymod & enums::EventState::Alt == enums::EventState::Alt),
+        ctrl: (keymod & enums::EventState::Ctrl == enums::EventState::Ctrl),
+        shift: (keymod & enums::EventState::Shift == enums::EventState::Shift),
+        mac_cmd: keymod & enums::EventState::Meta == enums::EventState::Meta,
+
+        //TOD: Test on both windows and mac
+        command: (keymod & enums::EventState::Command == enums::EventState::Command),
+    }
+}
+
+/// FLTK mouse buttons, the side buttons are reported since FLTK 1.4.
+const FL_LEFT_MOUSE: i32 = 1;
+const FL_MIDDLE_MOUSE: i32 = 2;
+const FL_RIGHT_MOUSE: i32 = 3;
+const FL_BACK_MOUSE: i32 = 4;
+const FL_FORWARD_MOUSE: i32 = 5;
+/// First function key (`FL_F`), F1 is `FL_F + 1`.
+const FL_F: i32 = 0xffbd;
+/// First keypad key (`FL_KP`), keypad keys are `FL_KP + ascii`.
+const FL_KP: i32 = 0xff80;
+/// Clipboard keys found on some keyboards, reported as XF86 keysyms on X11.
+const XF86_COPY: i32 = 0x1008ff57;
+const XF86_CUT: i32 = 0x1008ff58;
+const XF86_PASTE: i32 = 0x1008ff6d;
+
+const FUNCTION_KEYS: [egui::Key; 35] = [
+    egui::Key::F1,
+    egui::Key::F2,
+    egui::Key::F3,
+    egui::Key::F4,
+    egui::Key::F5,
+    egui::Key::F6,
+    egui::Key::F7,
+    egui::Key::F8,
+    egui::Key::F9,
+    egui::Key::F10,
+    egui::Key::F11,
+    egui::Key::F12,
+    egui::Key::F13,
+    egui::Key::F14,
+    egui::Key::F15,
+    egui::Key::F16,
+    egui::Key::F17,
+    egui::Key::F18,
+    egui::Key::F19,
+    egui::Key::F20,
+    egui::Key::F21,
+    egui::Key::F22,
+    egui::Key::F23,
+    egui::Key::F24,
+    egui::Key::F25,
+    egui::Key::F26,
+    egui::Key::F27,
+    egui::Key::F28,
+    egui::Key::F29,
+    egui::Key::F30,
+    egui::Key::F31,
+    egui::Key::F32,
+    egui::Key::F33,
+    egui::Key::F34,
+    egui::Key::F35,
+];
+
 /// Translates key codes
 pub fn translate_virtual_key_code(key: enums::Key) -> Option<egui::Key> {
     match key {
@@ -342,53 +945,81 @@ pub fn translate_virtual_key_code(key: enums::Key) -> Option<egui::Key> {
         enums::Key::End => Some(egui::Key::End),
         enums::Key::PageDown => Some(egui::Key::PageDown),
         enums::Key::PageUp => Some(egui::Key::PageUp),
-        enums::Key::Enter => Some(egui::Key::Enter),
-        _ => {
-            if let Some(k) = key.to_char() {
-                match k {
-                    ' ' => Some(egui::Key::Space),
-                    'a' => Some(egui::Key::A),
-                    'b' => Some(egui::Key::B),
-                    'c' => Some(egui::Key::C),
-                    'd' => Some(egui::Key::D),
-                    'e' => Some(egui::Key::E),
-                    'f' => Some(egui::Key::F),
-                    'g' => Some(egui::Key::G),
-                    'h' => Some(egui::Key::H),
-                    'i' => Some(egui::Key::I),
-                    'j' => Some(egui::Key::J),
-                    'k' => Some(egui::Key::K),
-                    'l' => Some(egui::Key::L),
-                    'm' => Some(egui::Key::M),
-                    'n' => Some(egui::Key::N),
-                    'o' => Some(egui::Key::O),
-                    'p' => Some(egui::Key::P),
-                    'q' => Some(egui::Key::Q),
-                    'r' => Some(egui::Key::R),
-                    's' => Some(egui::Key::S),
-                    't' => Some(egui::Key::T),
-                    'u' => Some(egui::Key::U),
-                    'v' => Some(egui::Key::V),
-                    'w' => Some(egui::Key::W),
-                    'x' => Some(egui::Key::X),
-                    'y' => Some(egui::Key::Y),
-                    'z' => Some(egui::Key::Z),
-                    '0' => Some(egui::Key::Num0),
-                    '1' => Some(egui::Key::Num1),
-                    '2' => Some(egui::Key::Num2),
-                    '3' => Some(egui::Key::Num3),
-                    '4' => Some(egui::Key::Num4),
-                    '5' => Some(egui::Key::Num5),
-                    '6' => Some(egui::Key::Num6),
-                    '7' => Some(egui::Key::Num7),
-                    '8' => Some(egui::Key::Num8),
-                    '9' => Some(egui::Key::Num9),
-                    _ => None,
-                }
-            } else {
-                None
+        enums::Key::Enter | enums::Key::KPEnter => Some(egui::Key::Enter),
+        _ => match key.bits() {
+            XF86_COPY => Some(egui::Key::Copy),
+            XF86_CUT => Some(egui::Key::Cut),
+            XF86_PASTE => Some(egui::Key::Paste),
+            bits if bits > FL_F && bits <= FL_F + FUNCTION_KEYS.len() as i32 => {
+                Some(FUNCTION_KEYS[(bits - FL_F - 1) as usize])
             }
-        }
+            // Numpad keys map to the same egui keys as the main keyboard.
+            bits if bits > FL_KP && bits < FL_F => {
+                char::from_u32((bits - FL_KP) as u32).and_then(translate_char)
+            }
+            // Other FLTK key codes above Latin-1 are keysyms, `to_char` would truncate them.
+            bits @ 0..=0xff => translate_char(char::from(bits as u8)),
+            _ => None,
+        },
+    }
+}
+
+/// Translates the character printed on a key, regardless of shift state
+fn translate_char(c: char) -> Option<egui::Key> {
+    match c.to_ascii_lowercase() {
+        ' ' => Some(egui::Key::Space),
+        'a' => Some(egui::Key::A),
+        'b' => Some(egui::Key::B),
+        'c' => Some(egui::Key::C),
+        'd' => Some(egui::Key::D),
+        'e' => Some(egui::Key::E),
+        'f' => Some(egui::Key::F),
+        'g' => Some(egui::Key::G),
+        'h' => Some(egui::Key::H),
+        'i' => Some(egui::Key::I),
+        'j' => Some(egui::Key::J),
+        'k' => Some(egui::Key::K),
+        'l' => Some(egui::Key::L),
+        'm' => Some(egui::Key::M),
+        'n' => Some(egui::Key::N),
+        'o' => Some(egui::Key::O),
+        'p' => Some(egui::Key::P),
+        'q' => Some(egui::Key::Q),
+        'r' => Some(egui::Key::R),
+        's' => Some(egui::Key::S),
+        't' => Some(egui::Key::T),
+        'u' => Some(egui::Key::U),
+        'v' => Some(egui::Key::V),
+        'w' => Some(egui::Key::W),
+        'x' => Some(egui::Key::X),
+        'y' => Some(egui::Key::Y),
+        'z' => Some(egui::Key::Z),
+        '0' => Some(egui::Key::Num0),
+        '1' => Some(egui::Key::Num1),
+        '2' => Some(egui::Key::Num2),
+        '3' => Some(egui::Key::Num3),
+        '4' => Some(egui::Key::Num4),
+        '5' => Some(egui::Key::Num5),
+        '6' => Some(egui::Key::Num6),
+        '7' => Some(egui::Key::Num7),
+        '8' => Some(egui::Key::Num8),
+        '9' => Some(egui::Key::Num9),
+        ':' => Some(egui::Key::Colon),
+        ',' => Some(egui::Key::Comma),
+        '\\' => Some(egui::Key::Backslash),
+        '/' => Some(egui::Key::Slash),
+        '|' => Some(egui::Key::Pipe),
+        '?' => Some(egui::Key::Questionmark),
+        '[' => Some(egui::Key::OpenBracket),
+        ']' => Some(egui::Key::CloseBracket),
+        '`' => Some(egui::Key::Backtick),
+        '-' => Some(egui::Key::Minus),
+        '.' => Some(egui::Key::Period),
+        '+' => Some(egui::Key::Plus),
+        '=' => Some(egui::Key::Equals),
+        ';' => Some(egui::Key::Semicolon),
+        '\'' => Some(egui::Key::Quote),
+        _ => None,
     }
 }
 
@@ -398,31 +1029,38 @@ pub fn translate_cursor(
     fused: &mut FusedCursor,
     cursor_icon: egui::CursorIcon,
 ) {
-    let tmp_icon = match cursor_icon {
-        CursorIcon::None => enums::Cursor::None,
-        CursorIcon::Default => enums::Cursor::Arrow,
-        CursorIcon::Help => enums::Cursor::Help,
-        CursorIcon::PointingHand => enums::Cursor::Hand,
-        CursorIcon::ResizeHorizontal => enums::Cursor::WE,
-        CursorIcon::ResizeNeSw => enums::Cursor::NESW,
-        CursorIcon::ResizeNwSe => enums::Cursor::NWSE,
-        CursorIcon::ResizeVertical => enums::Cursor::NS,
-        CursorIcon::Text => enums::Cursor::Insert,
-        CursorIcon::Crosshair => enums::Cursor::Cross,
-        CursorIcon::NotAllowed | CursorIcon::NoDrop => enums::Cursor::Wait,
-        CursorIcon::Wait => enums::Cursor::Wait,
-        CursorIcon::Progress => enums::Cursor::Wait,
-        CursorIcon::Grab => enums::Cursor::Hand,
-        CursorIcon::Grabbing => enums::Cursor::Move,
-        CursorIcon::Move => enums::Cursor::Move,
-
-        _ => enums::Cursor::Arrow,
-    };
+    if fused.current == Some(cursor_icon) {
+        return;
+    }
+    fused.current = Some(cursor_icon);
 
-    if tmp_icon != fused.cursor_icon {
-        fused.cursor_icon = tmp_icon;
-        win.set_cursor(tmp_icon)
+    let registered = fused.images.iter().find(|(icon, _)| *icon == cursor_icon);
+    if let Some((_, image)) = registered {
+        if image.apply(win) {
+            return;
+        }
     }
+    let tmp_icon = match cursor::stock_cursor(cursor_icon) {
+        Some(stock) => stock,
+        None => match cursor::bundled_cursor(cursor_icon) {
+            Some(image) if image.apply(win) => return,
+            _ => ARROW,
+        },
+    };
+    fused.cursor_icon = tmp_icon;
+    win.set_cursor(tmp_icon)
+}
+
+/// Converts an FLTK image to an egui image.
+fn color_image_from_rgb<I: ImageExt>(image: &I) -> Result<egui::ColorImage, FltkError> {
+    let size = [image.data_w() as usize, image.data_h() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        &image
+            .to_rgb()?
+            .convert(enums::ColorDepth::Rgba8)?
+            .to_rgb_data(),
+    ))
 }
 
 pub trait EguiImageConvertible<I>
@@ -517,7 +1155,7 @@ impl ColorImageExt for egui::ColorImage {
 
     fn from_color32_slice(size: [usize; 2], slice: &[egui::Color32]) -> Self {
         let mut pixels: Vec<u8> = Vec::with_capacity(slice.len() * 4);
-        slice.into_iter().for_each(|x| {
+        slice.iter().for_each(|x| {
             pixels.push(x[0]);
             pixels.push(x[1]);
             pixels.push(x[2]);
@@ -613,7 +1251,7 @@ impl TextureHandleExt for egui::TextureHandle {
         options: egui::TextureOptions,
     ) -> Self {
         let mut pixels: Vec<u8> = Vec::with_capacity(slice.len() * 4);
-        slice.into_iter().for_each(|x| {
+        slice.iter().for_each(|x| {
             pixels.push(x[0]);
             pixels.push(x[1]);
             pixels.push(x[2]);
@@ -623,3 +1261,267 @@ impl TextureHandleExt for egui::TextureHandle {
         ctx.load_texture(debug_name, color_image, options)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> EguiState {
+        EguiState::with_canvas([800, 600], 1.0, 2048)
+    }
+
+    fn ime(event: ImeEvent) -> Event {
+        Event::Ime(event)
+    }
+
+    #[test]
+    fn compose_without_marked_text_is_plain_text() {
+        let mut state = state();
+        state.fuse_compose(0, "a", 0);
+        state.fuse_compose(0, "\u{8}", 0);
+        state.fuse_compose(0, "", 0);
+        assert_eq!(state.input.events, [Event::Text("a".to_owned())]);
+    }
+
+    #[test]
+    fn compose_preedit_then_commit() {
+        let mut state = state();
+        state.fuse_compose(0, "k", 1);
+        state.fuse_compose(1, "か", 3);
+        state.fuse_compose(3, "漢", 0);
+        assert_eq!(
+            state.input.events,
+            [
+                ime(ImeEvent::Enabled),
+                ime(ImeEvent::Preedit("k".to_owned())),
+                ime(ImeEvent::Preedit("か".to_owned())),
+                ime(ImeEvent::Preedit(String::new())),
+                ime(ImeEvent::Commit("漢".to_owned())),
+                ime(ImeEvent::Disabled),
+            ]
+        );
+        assert!(state.ime_preedit.is_none());
+    }
+
+    #[test]
+    fn compose_commits_unmarked_text() {
+        let mut state = state();
+        state.fuse_compose(0, "ㅎ", 3);
+        state.fuse_compose(3, "하", 3);
+        // Only the new syllable stays marked, the one before is final.
+        state.fuse_compose(0, "ㄴ", 3);
+        assert_eq!(state.ime_preedit.as_deref(), Some("ㄴ"));
+        assert_eq!(
+            state.input.events[3..],
+            [
+                ime(ImeEvent::Preedit(String::new())),
+                ime(ImeEvent::Commit("하".to_owned())),
+                ime(ImeEvent::Preedit("ㄴ".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn compose_deletes_whole_characters() {
+        let mut state = state();
+        state.fuse_compose(0, "か", 3);
+        // Deleting part of a character removes all of it.
+        state.fuse_compose(1, "a", 1);
+        assert_eq!(state.ime_preedit.as_deref(), Some("a"));
+        state.fuse_compose(10, "b", 1);
+        assert_eq!(state.ime_preedit.as_deref(), Some("b"));
+    }
+
+    /// FLTK key codes, and the egui key each one maps to.
+    fn key_table() -> Vec<(enums::Key, Key)> {
+        let mut table = vec![
+            (enums::Key::Left, Key::ArrowLeft),
+            (enums::Key::Up, Key::ArrowUp),
+            (enums::Key::Right, Key::ArrowRight),
+            (enums::Key::Down, Key::ArrowDown),
+            (enums::Key::Escape, Key::Escape),
+            (enums::Key::Tab, Key::Tab),
+            (enums::Key::BackSpace, Key::Backspace),
+            (enums::Key::Insert, Key::Insert),
+            (enums::Key::Home, Key::Home),
+            (enums::Key::Delete, Key::Delete),
+            (enums::Key::End, Key::End),
+            (enums::Key::PageDown, Key::PageDown),
+            (enums::Key::PageUp, Key::PageUp),
+            (enums::Key::Enter, Key::Enter),
+            (enums::Key::KPEnter, Key::Enter),
+            (enums::Key::from_i32(XF86_COPY), Key::Copy),
+            (enums::Key::from_i32(XF86_CUT), Key::Cut),
+            (enums::Key::from_i32(XF86_PASTE), Key::Paste),
+        ];
+        let chars = [
+            (' ', Key::Space),
+            (':', Key::Colon),
+            (',', Key::Comma),
+            ('\\', Key::Backslash),
+            ('/', Key::Slash),
+            ('|', Key::Pipe),
+            ('?', Key::Questionmark),
+            ('[', Key::OpenBracket),
+            (']', Key::CloseBracket),
+            ('`', Key::Backtick),
+            ('-', Key::Minus),
+            ('.', Key::Period),
+            ('+', Key::Plus),
+            ('=', Key::Equals),
+            (';', Key::Semicolon),
+            ('\'', Key::Quote),
+        ];
+        table.extend(chars.map(|(c, key)| (enums::Key::from_char(c), key)));
+        for c in ('a'..='z').chain('0'..='9') {
+            let key = Key::from_name(&c.to_ascii_uppercase().to_string()).unwrap();
+            table.push((enums::Key::from_char(c), key));
+            // With shift held
+            table.push((enums::Key::from_char(c.to_ascii_uppercase()), key));
+        }
+        for c in ['0', '5', '9', '+', '-', '.', '/', '='] {
+            let key = translate_char(c).unwrap();
+            table.push((enums::Key::from_i32(FL_KP + c as i32), key));
+        }
+        for n in 1..=35 {
+            let key = Key::from_name(&format!("F{n}")).unwrap();
+            table.push((enums::Key::from_i32(FL_F + n), key));
+        }
+        table
+    }
+
+    #[test]
+    fn fltk_keys_translate_to_egui_keys() {
+        for (fltk_key, key) in key_table() {
+            assert_eq!(
+                translate_virtual_key_code(fltk_key),
+                Some(key),
+                "FLTK key {:#x}",
+                fltk_key.bits()
+            );
+        }
+    }
+
+    #[test]
+    fn every_egui_key_has_an_fltk_key() {
+        let table = key_table();
+        for key in Key::ALL {
+            assert!(
+                table.iter().any(|(_, mapped)| mapped == key),
+                "{key:?} has no FLTK key"
+            );
+        }
+    }
+
+    #[test]
+    fn other_fltk_keys_are_dropped() {
+        for fltk_key in [
+            enums::Key::ShiftL,
+            enums::Key::ControlL,
+            enums::Key::AltL,
+            enums::Key::CapsLock,
+            enums::Key::Menu,
+            enums::Key::Print,
+            enums::Key::from_i32(FL_KP + '*' as i32),
+            enums::Key::from_char('*'),
+            enums::Key::from_char('é'),
+        ] {
+            assert_eq!(
+                translate_virtual_key_code(fltk_key),
+                None,
+                "FLTK key {:#x}",
+                fltk_key.bits()
+            );
+        }
+    }
+
+    #[test]
+    fn fltk_mouse_buttons_translate_to_egui_buttons() {
+        use egui::PointerButton;
+        for (button, expected) in [
+            (1, Some(PointerButton::Primary)),
+            (2, Some(PointerButton::Middle)),
+            (3, Some(PointerButton::Secondary)),
+            (4, Some(PointerButton::Extra1)),
+            (5, Some(PointerButton::Extra2)),
+            (0, None),
+            (6, None),
+            (-1, None),
+        ] {
+            assert_eq!(translate_mouse_button(button), expected, "button {button}");
+        }
+    }
+
+    fn touch(device: u64, id: u64, phase: egui::TouchPhase, x: f32) -> TouchPoint {
+        TouchPoint {
+            device_id: egui::TouchDeviceId(device),
+            id,
+            phase,
+            x,
+            y: 10.0,
+            force: None,
+        }
+    }
+
+    /// The (device, id, phase) of the touch events sent to egui.
+    fn touch_events(state: &mut EguiState) -> Vec<(u64, u64, egui::TouchPhase)> {
+        state
+            .take_input()
+            .events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Touch {
+                    device_id,
+                    id,
+                    phase,
+                    ..
+                } => Some((device_id.0, id.0, phase)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn touches_keep_their_ids_and_phases() {
+        use egui::TouchPhase::*;
+        let mut state = state();
+        state.fuse_touch(touch(1, 7, Start, 10.0));
+        state.fuse_touch(touch(1, 8, Start, 20.0));
+        state.fuse_touch(touch(1, 7, Move, 15.0));
+        state.fuse_touch(touch(1, 7, End, 15.0));
+        state.fuse_touch(touch(2, 7, Start, 30.0));
+        state.fuse_touch(touch(2, 7, Cancel, 30.0));
+        assert_eq!(
+            touch_events(&mut state),
+            [
+                (1, 7, Start),
+                (1, 8, Start),
+                (1, 7, Move),
+                (1, 7, End),
+                (2, 7, Start),
+                (2, 7, Cancel),
+            ]
+        );
+        // Only the second finger is still down.
+        assert_eq!(state.touches.len(), 1);
+        assert_eq!(state.touches[&(1, 8)].x, 20.0);
+    }
+
+    #[test]
+    fn pen_samples_carry_pressure_and_scale() {
+        let mut state = state();
+        state.set_visual_scale(2.0);
+        state.fuse_touch(TouchPoint::pen(egui::TouchPhase::Start, 40.0, 20.0, 0.5));
+        let events = state.take_input().events;
+        assert_eq!(
+            events,
+            [Event::Touch {
+                device_id: touch::PEN_DEVICE,
+                id: egui::TouchId(0),
+                phase: egui::TouchPhase::Start,
+                pos: pos2(20.0, 10.0),
+                force: Some(0.5),
+            }]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    sync::Arc,
+    time::Instant,
+};
+
+use egui::{
+    DeferredViewportUiCallback, ImmediateViewport, ViewportBuilder, ViewportCommand, ViewportId,
+    ViewportIdMap, ViewportOutput,
+};
+use egui_glow::Painter;
+use fltk::{
+    app, enums,
+    prelude::{GroupExt, WidgetBase, WidgetExt, WindowExt},
+    window::GlWindow,
+};
+
+use crate::{EguiState, RepaintScheduler};
+
+/// The window of a viewport other than the root one.
+struct ViewportWindow {
+    win: GlWindow,
+    state: Rc<RefCell<EguiState>>,
+    /// The builder the window was last updated from.
+    builder: ViewportBuilder,
+    /// `None` for immediate viewports, which are drawn during their parent's frame.
+    ui: Option<Arc<DeferredViewportUiCallback>>,
+    /// Commands waiting for the state, which is busy while the viewport runs.
+    commands: Vec<ViewportCommand>,
+}
+
+thread_local! {
+    /// The managers of this thread. egui keeps a single immediate viewport renderer
+    /// per thread, which looks up the manager of the context it renders for.
+    static MANAGERS: RefCell<Vec<Weak<RefCell<ViewportManager>>>> = RefCell::default();
+}
+
+/// Opens a `GlWindow` for each viewport egui shows with `show_viewport_deferred`
+/// and `show_viewport_immediate`, and closes it once egui stops showing it.
+///
+/// The windows use the root window's GL context, so one painter and one set of
+/// textures serve all of them.
+pub(crate) struct ViewportManager {
+    ctx: egui::Context,
+    root: GlWindow,
+    painter: Rc<RefCell<Painter>>,
+    scheduler: RepaintScheduler,
+    windows: ViewportIdMap<ViewportWindow>,
+    /// Windows of viewports shown by someone else, see [`Self::add_external`].
+    external: ViewportIdMap<GlWindow>,
+    /// Commands for the root viewport sent during any frame, see [`Self::take_root_commands`].
+    root_commands: Vec<ViewportCommand>,
+    this: Weak<RefCell<ViewportManager>>,
+    /// The time the root window reports to egui is measured from.
+    start_time: Instant,
+}
+
+impl ViewportManager {
+    /// Manage the viewports of `ctx`, which is shown in `root`, with `input.time`
+    /// measured from `start_time` like the root's.
+    ///
+    /// This turns off embedded viewports and installs the renderer of immediate viewports,
+    /// which serves the managers of all contexts on this thread.
+    pub(crate) fn new(
+        ctx: &egui::Context,
+        root: &GlWindow,
+        painter: Rc<RefCell<Painter>>,
+        scheduler: RepaintScheduler,
+        start_time: Instant,
+    ) -> Rc<RefCell<Self>> {
+        let manager = Rc::new_cyclic(|this| {
+            RefCell::new(Self {
+                ctx: ctx.clone(),
+                root: root.clone(),
+                painter,
+                scheduler,
+                windows: ViewportIdMap::default(),
+                external: ViewportIdMap::default(),
+                root_commands: Vec::new(),
+                this: this.clone(),
+                start_time,
+            })
+        });
+        ctx.set_embed_viewports(false);
+        MANAGERS.with(|managers| {
+            let mut managers = managers.borrow_mut();
+            managers.retain(|manager| manager.strong_count() > 0);
+            managers.push(Rc::downgrade(&manager));
+        });
+        egui::Context::set_immediate_viewport_renderer(|ctx, immediate| {
+            if let Some(manager) = Self::of(ctx) {
+                Self::run_immediate(&manager, ctx, immediate);
+            }
+        });
+        manager
+    }
+
+    /// The manager of `ctx` on this thread, if any.
+    fn of(ctx: &egui::Context) -> Option<Rc<RefCell<Self>>> {
+        MANAGERS.with(|managers| {
+            managers
+                .borrow()
+                .iter()
+                .filter_map(Weak::upgrade)
+                .find(|manager| manager.borrow().ctx == *ctx)
+        })
+    }
+
+    /// Open, update and close windows after a frame, and schedule their repaints.
+    ///
+    /// Only the root frame lists all the viewports still shown, windows missing from
+    /// its output are closed.
+    pub(crate) fn handle_output(
+        manager: &Rc<RefCell<Self>>,
+        viewport_output: ViewportIdMap<ViewportOutput>,
+        root_frame: bool,
+    ) {
+        for (&viewport_id, output) in &viewport_output {
+            let ViewportOutput {
+                builder,
+                viewport_ui_cb,
+                commands,
+                repaint_delay,
+                ..
+            } = output.clone();
+            if viewport_id == ViewportId::ROOT {
+                manager.borrow_mut().root_commands.extend(commands);
+            } else if manager.borrow().external.contains_key(&viewport_id) {
+                continue;
+            } else {
+                Self::open_or_update(manager, viewport_id, builder);
+                if let Some(window) = manager.borrow_mut().windows.get_mut(&viewport_id) {
+                    window.ui = viewport_ui_cb;
+                    window.commands.extend(commands);
+                }
+                Self::apply_commands(manager, viewport_id);
+            }
+            manager
+                .borrow()
+                .scheduler
+                .schedule_viewport(viewport_id, repaint_delay);
+        }
+
+        if root_frame {
+            let closed: Vec<ViewportId> = manager
+                .borrow()
+                .windows
+                .keys()
+                .filter(|viewport_id| !viewport_output.contains_key(viewport_id))
+                .copied()
+                .collect();
+            for viewport_id in closed {
+                manager.borrow_mut().close(viewport_id);
+            }
+        }
+    }
+
+    /// Leave the viewport shown in `win` to its owner, which applies its output.
+    pub(crate) fn add_external(&mut self, viewport_id: ViewportId, win: &GlWindow) {
+        self.external.insert(viewport_id, win.clone());
+    }
+
+    /// The commands sent to the root viewport, apply them to the root window.
+    pub(crate) fn take_root_commands(&mut self) -> Vec<ViewportCommand> {
+        std::mem::take(&mut self.root_commands)
+    }
+
+    /// Close all the windows, before the GL context goes away with the root window.
+    pub(crate) fn close_all(&mut self) {
+        let viewport_ids: Vec<ViewportId> = self.windows.keys().copied().collect();
+        for viewport_id in viewport_ids {
+            self.close(viewport_id);
+        }
+    }
+
+    fn close(&mut self, viewport_id: ViewportId) {
+        if let Some(mut window) = self.windows.remove(&viewport_id) {
+            self.scheduler.remove_viewport(viewport_id);
+            window.win.hide();
+            // FLTK deletes it once the current event or draw is done.
+            app::delete_widget(window.win);
+        }
+    }
+
+    /// Open the window of `viewport_id` if needed, and queue the changes to `builder`.
+    fn open_or_update(
+        manager: &Rc<RefCell<Self>>,
+        viewport_id: ViewportId,
+        builder: ViewportBuilder,
+    ) {
+        let mut this = manager.borrow_mut();
+        if !this.windows.contains_key(&viewport_id) {
+            let window = this.open(viewport_id);
+            this.windows.insert(viewport_id, window);
+        }
+        let window = this.windows.get_mut(&viewport_id).unwrap();
+        let (commands, _recreate) = window.builder.patch(builder);
+        window.commands.extend(commands);
+    }
+
+    /// Apply the queued commands, unless the viewport is running.
+    fn apply_commands(manager: &Rc<RefCell<Self>>, viewport_id: ViewportId) {
+        let mut this = manager.borrow_mut();
+        let Some(window) = this.windows.get_mut(&viewport_id) else {
+            return;
+        };
+        let (state, mut win) = (window.state.clone(), window.win.clone());
+        let Ok(mut state) = state.try_borrow_mut() else {
+            return;
+        };
+        let commands = std::mem::take(&mut window.commands);
+        drop(this);
+        state.fuse_viewport_commands(&mut win, commands);
+    }
+
+    fn open(&mut self, viewport_id: ViewportId) -> ViewportWindow {
+        let mut win = GlWindow::default().with_size(400, 300).center_screen();
+        win.set_mode(enums::Mode::Opengl3);
+        win.end();
+        win.make_resizable(true);
+        win.show();
+        // Draw with the root window's context, FLTK keeps it alive when this window closes.
+        if let Some(context) = self.root.context() {
+            win.set_context(context, false);
+        }
+
+        let mut state = EguiState::new(&win, self.painter.borrow().max_texture_side());
+        state.viewport_id = viewport_id;
+        let state = Rc::new(RefCell::new(state));
+
+        win.handle({
+            let state = state.clone();
+            move |win, ev| match ev {
+                ev if crate::is_fused_event(ev) => {
+                    if let Ok(mut state) = state.try_borrow_mut() {
+                        let consumed = state.fuse_input(win, ev);
+                        win.redraw();
+                        consumed
+                    } else {
+                        false
+                    }
+                }
+                _ => false,
+            }
+        });
+
+        // egui tells the app, which stops showing the viewport unless it cancels.
+        win.set_callback({
+            let state = state.clone();
+            move |win| {
+                if app::event() == enums::Event::Close {
+                    if let Ok(mut state) = state.try_borrow_mut() {
+                        state.request_close();
+                        win.redraw();
+                    }
+                }
+            }
+        });
+
+        win.draw({
+            let manager = self.this.clone();
+            move |win| {
+                if let Some(manager) = manager.upgrade() {
+                    Self::run_deferred(&manager, viewport_id, win);
+                }
+            }
+        });
+
+        self.scheduler.add_viewport(viewport_id, &win);
+        ViewportWindow {
+            win,
+            state,
+            builder: ViewportBuilder::default(),
+            ui: None,
+            commands: Vec::new(),
+        }
+    }
+
+    /// The draw callback of a viewport's window.
+    fn run_deferred(manager: &Rc<RefCell<Self>>, viewport_id: ViewportId, win: &mut GlWindow) {
+        let this = manager.borrow();
+        let Some(window) = this.windows.get(&viewport_id) else {
+            return;
+        };
+        let Some(ui) = window.ui.clone() else {
+            // Immediate viewports can only be drawn by their parent.
+            this.ctx.request_repaint_of(ViewportId::ROOT);
+            return;
+        };
+        let (ctx, state) = (this.ctx.clone(), window.state.clone());
+        drop(this);
+
+        Self::apply_commands(manager, viewport_id);
+        let mut state = state.borrow_mut();
+        let egui_output = Self::run(manager, &ctx, &mut state, win, |ctx| ui(ctx));
+        drop(state);
+        Self::handle_output(manager, egui_output, false);
+    }
+
+    /// The renderer egui calls for `show_viewport_immediate`, during the parent's frame.
+    fn run_immediate(
+        manager: &Rc<RefCell<Self>>,
+        ctx: &egui::Context,
+        immediate: ImmediateViewport,
+    ) {
+        let ImmediateViewport {
+            ids,
+            builder,
+            mut viewport_ui_cb,
+        } = immediate;
+        Self::open_or_update(manager, ids.this, builder);
+        Self::apply_commands(manager, ids.this);
+        let Some((mut win, state)) = manager
+            .borrow()
+            .windows
+            .get(&ids.this)
+            .map(|window| (window.win.clone(), window.state.clone()))
+        else {
+            return;
+        };
+
+        win.make_current();
+        let mut state = state.borrow_mut();
+        let egui_output = Self::run(manager, ctx, &mut state, &mut win, |ctx| {
+            viewport_ui_cb(ctx)
+        });
+        drop(state);
+        win.swap_buffers();
+        // Back to the window being drawn.
+        let mut parent = {
+            let this = manager.borrow();
+            match this.windows.get(&ids.parent) {
+                Some(window) => window.win.clone(),
+                None => this.external.get(&ids.parent).unwrap_or(&this.root).clone(),
+            }
+        };
+        parent.make_current();
+        Self::handle_output(manager, egui_output, false);
+    }
+
+    /// Run and paint a frame of the viewport shown in `win`, whose GL context is current.
+    fn run(
+        manager: &Rc<RefCell<Self>>,
+        ctx: &egui::Context,
+        state: &mut EguiState,
+        win: &mut GlWindow,
+        mut ui: impl FnMut(&egui::Context),
+    ) -> ViewportIdMap<ViewportOutput> {
+        let (painter, start_time) = {
+            let this = manager.borrow();
+            (this.painter.clone(), this.start_time)
+        };
+        state.fuse_window_scale(win);
+        state.fuse_viewport_info(win);
+        painter.borrow_mut().clear(
+            state.canvas_size,
+            ctx.style().visuals.panel_fill.to_normalized_gamma_f32(),
+        );
+
+        state.input.time = Some(start_time.elapsed().as_secs_f64());
+        let egui_output = ctx.run(state.take_input(), |ctx| {
+            ui(ctx);
+            state.update_from_viewport(ctx);
+        });
+
+        state.fuse_output(win, egui_output.platform_output);
+        state.update_from_context(ctx);
+        if state.is_scrolling() {
+            ctx.request_repaint_of(state.viewport_id);
+        }
+        if std::mem::take(&mut state.close_requested) {
+            // The app decides in its next frame whether the viewport stays.
+            ctx.request_repaint_of(ViewportId::ROOT);
+        }
+        let meshes = ctx.tessellate(egui_output.shapes, egui_output.pixels_per_point);
+        painter.borrow_mut().paint_and_update_textures(
+            state.canvas_size,
+            egui_output.pixels_per_point,
+            &meshes,
+            &egui_output.textures_delta,
+        );
+        egui_output.viewport_output
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use egui::{ViewportId, ViewportIdMap};
+use fltk::{app, prelude::WidgetExt, window::GlWindow};
+
+/// Turns egui's repaint requests into redraws of an FLTK window.
+///
+/// Redraws are scheduled with FLTK timeouts, so the event loop sleeps while egui is idle.
+/// Requests made outside of a frame, including from other threads, wake the loop with
+/// [`app::awake`] and are picked up before FLTK waits for events again.
+///
+/// Clones share the same timeouts. The last one dropped stops scheduling redraws.
+#[derive(Clone)]
+pub struct RepaintScheduler {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    /// The window of each viewport, the root one is the window passed to
+    /// [`RepaintScheduler::new`].
+    windows: RefCell<ViewportIdMap<GlWindow>>,
+    /// Earliest repaint asked for through the context's repaint callback.
+    requested: Arc<Mutex<ViewportIdMap<Instant>>>,
+    /// The pending timeouts, and when they fire.
+    scheduled: Rc<RefCell<ViewportIdMap<(Instant, app::TimeoutHandle)>>>,
+    /// The check callback picking up the requests, which holds no reference to the scheduler.
+    check: app::CheckHandle,
+}
+
+impl RepaintScheduler {
+    /// Redraw `win` whenever `ctx` asks for a repaint.
+    ///
+    /// This replaces any repaint callback previously set on `ctx`.
+    pub fn new(ctx: &egui::Context, win: &GlWindow) -> Self {
+        let requested: Arc<Mutex<ViewportIdMap<Instant>>> = Arc::default();
+        ctx.set_request_repaint_callback({
+            let requested = requested.clone();
+            move |info| {
+                if let Some(at) = Instant::now().checked_add(info.delay) {
+                    let mut requested = requested.lock().unwrap();
+                    let at = requested
+                        .get(&info.viewport_id)
+                        .map_or(at, |&prev| prev.min(at));
+                    requested.insert(info.viewport_id, at);
+                    app::awake();
+                }
+            }
+        });
+        let mut windows = ViewportIdMap::default();
+        windows.insert(ViewportId::ROOT, win.clone());
+        let inner = Rc::new_cyclic(|this: &Weak<Inner>| Inner {
+            windows: RefCell::new(windows),
+            requested,
+            scheduled: Rc::default(),
+            check: app::add_check({
+                let this = this.clone();
+                move |_| {
+                    if let Some(inner) = this.upgrade() {
+                        inner.poll();
+                    }
+                }
+            }),
+        });
+        Self { inner }
+    }
+
+    /// Schedule the next redraw, call this after each frame with the
+    /// `repaint_delay` of the frame's `ViewportOutput`.
+    pub fn schedule(&self, repaint_delay: Duration) {
+        self.schedule_viewport(ViewportId::ROOT, repaint_delay);
+    }
+
+    /// Same as [`Self::schedule`], for the window of another viewport.
+    pub(crate) fn schedule_viewport(&self, viewport_id: ViewportId, repaint_delay: Duration) {
+        if let Some(at) = Instant::now().checked_add(repaint_delay) {
+            self.inner.schedule_at(viewport_id, at);
+        }
+        self.inner.poll();
+    }
+
+    /// Redraw `win` for the repaints of `viewport_id`.
+    pub(crate) fn add_viewport(&self, viewport_id: ViewportId, win: &GlWindow) {
+        self.inner
+            .windows
+            .borrow_mut()
+            .insert(viewport_id, win.clone());
+    }
+
+    pub(crate) fn remove_viewport(&self, viewport_id: ViewportId) {
+        self.inner.windows.borrow_mut().remove(&viewport_id);
+        if let Some((_, handle)) = self.inner.scheduled.borrow_mut().remove(&viewport_id) {
+            app::remove_timeout3(handle);
+        }
+    }
+}
+
+impl Inner {
+    /// Pick up the repaints requested through the context's callback.
+    fn poll(&self) {
+        let requested = std::mem::take(&mut *self.requested.lock().unwrap());
+        for (viewport_id, at) in requested {
+            self.schedule_at(viewport_id, at);
+        }
+    }
+
+    fn schedule_at(&self, viewport_id: ViewportId, at: Instant) {
+        let Some(win) = self.windows.borrow().get(&viewport_id).cloned() else {
+            return;
+        };
+        if let Some(&(scheduled_at, handle)) = self.scheduled.borrow().get(&viewport_id) {
+            if scheduled_at <= at {
+                return;
+            }
+            app::remove_timeout3(handle);
+        }
+        // Always go through a timeout: a redraw asked for while drawing would be lost.
+        let delay = at.saturating_duration_since(Instant::now()).as_secs_f64();
+        let handle = app::add_timeout3(delay, {
+            let mut win = win;
+            let scheduled = self.scheduled.clone();
+            move |_| {
+                scheduled.borrow_mut().remove(&viewport_id);
+                win.redraw();
+            }
+        });
+        self.scheduled
+            .borrow_mut()
+            .insert(viewport_id, (at, handle));
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        app::remove_check(self.check);
+        for (_, (_, handle)) in self.scheduled.borrow_mut().drain() {
+            app::remove_timeout3(handle);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+    time::Instant,
+};
+
+use egui_glow::glow;
+use fltk::{
+    app, enums,
+    prelude::{FltkError, GroupExt, WidgetBase, WidgetExt, WindowExt},
+    window::GlWindow,
+};
+
+use crate::{
+    multi_viewport::ViewportManager,
+    repaint::RepaintScheduler,
+    storage::{FileStorage, Storage},
+};
+
+/// The key the zoom factor is persisted under.
+const ZOOM_FACTOR_KEY: &str = "fltk_egui_zoom_factor";
+
+/// Implement this trait to write apps that can be run with [`run_native`].
+pub trait App {
+    /// Called each time the UI needs repainting.
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame);
+
+    /// Called once on shutdown, after [`Self::save`].
+    ///
+    /// The GL context is still current, so this is the place to free GL resources.
+    fn on_exit(&mut self, _gl: Option<&glow::Context>) {}
+
+    /// Called on shutdown so the app can persist its state.
+    ///
+    /// Only called when [`NativeOptions::persistence_path`] is set.
+    fn save(&mut self, _storage: &mut dyn Storage) {}
+
+    /// The color the window is cleared with before egui paints (normalized RGBA).
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+    }
+}
+
+/// Options controlling the window created by [`run_native`].
+pub struct NativeOptions {
+    /// Initial size of the window, default is 800x600.
+    pub inner_size: egui::Vec2,
+    /// Initial position of the window, centered on screen when `None`.
+    pub position: Option<egui::Pos2>,
+    /// default value is true
+    pub resizable: bool,
+    /// Request a multisampled GL context, default is false.
+    pub multisampling: bool,
+    /// Where [`App::save`] and the zoom factor are written to, nothing is persisted when `None`.
+    pub persistence_path: Option<PathBuf>,
+    /// Spread wheel scrolling over a few frames, default is false.
+    pub smooth_scrolling: bool,
+    /// Zoom the other FLTK widgets along with egui, see [`crate::EguiState::scale_fltk_widgets`].
+    /// Default is false.
+    pub scale_fltk_widgets: bool,
+}
+
+impl Default for NativeOptions {
+    fn default() -> Self {
+        Self {
+            inner_size: egui::vec2(800., 600.),
+            position: None,
+            resizable: true,
+            multisampling: false,
+            persistence_path: None,
+            smooth_scrolling: false,
+            scale_fltk_widgets: false,
+        }
+    }
+}
+
+/// Gives the app access to the window it runs in.
+pub struct Frame {
+    gl: Arc<glow::Context>,
+    window: GlWindow,
+    storage: Option<Box<dyn Storage>>,
+    /// Also set by the window's callback, which can't borrow the frame during a frame.
+    close_requested: Rc<Cell<bool>>,
+    image_paste_requested: bool,
+    pasted_image: Option<egui::ColorImage>,
+    copied_image: Option<egui::ColorImage>,
+    cursor_images: Vec<(egui::CursorIcon, egui::ColorImage, [i32; 2])>,
+}
+
+impl Frame {
+    /// Close the window and return from [`run_native`] after this frame.
+    pub fn close(&mut self) {
+        self.close_requested.set(true);
+        // Wake up the event loop so it notices.
+        app::awake();
+    }
+
+    /// The GL context egui paints with, useful for custom painting.
+    pub fn gl(&self) -> Option<&Arc<glow::Context>> {
+        Some(&self.gl)
+    }
+
+    /// The FLTK window hosting egui.
+    pub fn window(&self) -> &GlWindow {
+        &self.window
+    }
+
+    /// Ask for the clipboard image, it is returned by [`Self::take_pasted_image`] in a later frame.
+    pub fn request_image_paste(&mut self) {
+        self.image_paste_requested = true;
+    }
+
+    /// The image pasted since the last call, if any.
+    pub fn take_pasted_image(&mut self) -> Option<egui::ColorImage> {
+        self.pasted_image.take()
+    }
+
+    /// Put an image on the clipboard at the end of this frame.
+    pub fn copy_image(&mut self, image: egui::ColorImage) {
+        self.copied_image = Some(image);
+    }
+
+    /// Show `image` for the egui cursor `icon`, `hotspot` is the pixel at the pointer position.
+    pub fn set_cursor_image(
+        &mut self,
+        icon: egui::CursorIcon,
+        image: egui::ColorImage,
+        hotspot: [i32; 2],
+    ) {
+        self.cursor_images.push((icon, image, hotspot));
+    }
+
+    /// The storage the app was saved to last time, if persistence is enabled.
+    pub fn storage(&self) -> Option<&dyn Storage> {
+        self.storage.as_deref()
+    }
+
+    /// Mutable access to the storage, if persistence is enabled.
+    pub fn storage_mut(&mut self) -> Option<&mut (dyn Storage + 'static)> {
+        self.storage.as_deref_mut()
+    }
+}
+
+/// The parts of the runner the draw callback needs.
+struct Runner {
+    egui_app: Box<dyn App>,
+    frame: Frame,
+}
+
+/// Create a window, run `egui_app` in it and block until the window is closed.
+pub fn run_native(
+    title: &str,
+    options: NativeOptions,
+    egui_app: Box<dyn App>,
+) -> Result<(), FltkError> {
+    let fltk_app = app::App::default();
+    let mut win = GlWindow::default()
+        .with_size(options.inner_size.x as i32, options.inner_size.y as i32)
+        .with_label(title);
+    match options.position {
+        Some(pos) => win.set_pos(pos.x as i32, pos.y as i32),
+        None => win = win.center_screen(),
+    }
+    if options.multisampling {
+        win.set_mode(enums::Mode::Opengl3 | enums::Mode::MultiSample);
+    } else {
+        win.set_mode(enums::Mode::Opengl3);
+    }
+    win.end();
+    win.make_resizable(options.resizable);
+    win.show();
+    win.make_current();
+
+    let (painter, mut egui_state) = crate::init(&mut win);
+    egui_state.smooth_scrolling = options.smooth_scrolling;
+    egui_state.scale_fltk_widgets = options.scale_fltk_widgets;
+    let storage = options
+        .persistence_path
+        .map(|path| Box::new(FileStorage::from_path(path)) as Box<dyn Storage>);
+    if let Some(zoom) = storage
+        .as_ref()
+        .and_then(|storage| storage.get_string(ZOOM_FACTOR_KEY))
+        .and_then(|zoom| zoom.parse::<f32>().ok())
+        // A corrupt value would leave nothing on screen.
+        .filter(|zoom| zoom.is_finite() && *zoom > 0.0)
+    {
+        egui_state.set_visual_scale(zoom);
+    }
+    let state = Rc::new(RefCell::new(egui_state));
+    let egui_ctx = egui::Context::default();
+    let scheduler = RepaintScheduler::new(&egui_ctx, &win);
+    // Shared with the windows of the other viewports.
+    let painter = Rc::new(RefCell::new(painter));
+    let start_time = Instant::now();
+    let viewports = ViewportManager::new(&egui_ctx, &win, painter.clone(), scheduler, start_time);
+
+    let close_requested = Rc::new(Cell::new(false));
+    let runner = Rc::new(RefCell::new(Runner {
+        egui_app,
+        frame: Frame {
+            gl: painter.borrow().gl().clone(),
+            window: win.clone(),
+            storage,
+            close_requested: close_requested.clone(),
+            image_paste_requested: false,
+            pasted_image: None,
+            copied_image: None,
+            cursor_images: Vec::new(),
+        },
+    }));
+
+    win.handle({
+        let state = state.clone();
+        move |win, ev| match ev {
+            ev if crate::is_fused_event(ev) => {
+                if let Ok(mut state) = state.try_borrow_mut() {
+                    let consumed = state.fuse_input(win, ev);
+                    win.redraw();
+                    consumed
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    });
+
+    // The window is hidden after shutting down, while the GL context still exists.
+    // Closing it is up to egui, which can cancel it.
+    win.set_callback({
+        let close_requested = close_requested.clone();
+        let state = state.clone();
+        move |win| {
+            if app::event() == enums::Event::Close {
+                match state.try_borrow_mut() {
+                    Ok(mut state) => {
+                        state.request_close();
+                        win.redraw();
+                    }
+                    // The state is busy with a frame, close without asking egui.
+                    Err(_) => {
+                        close_requested.set(true);
+                        app::awake();
+                    }
+                }
+            }
+        }
+    });
+
+    win.draw({
+        let runner = runner.clone();
+        let state = state.clone();
+        let painter = painter.clone();
+        let viewports = viewports.clone();
+        move |win| {
+            let mut state = state.borrow_mut();
+            let Runner { egui_app, frame } = &mut *runner.borrow_mut();
+            // Catches scale changes of the screen that came without a resize.
+            state.fuse_window_scale(win);
+            state.fuse_viewport_info(win);
+            painter.borrow_mut().clear(
+                state.canvas_size,
+                egui_app.clear_color(&egui_ctx.style().visuals),
+            );
+
+            if let Some(image) = state.take_pasted_image() {
+                frame.pasted_image = Some(image);
+            }
+            state.input.time = Some(start_time.elapsed().as_secs_f64());
+            let egui_output = egui_ctx.run(state.take_input(), |ctx| egui_app.update(ctx, frame));
+
+            for (icon, image, hotspot) in frame.cursor_images.drain(..) {
+                state.set_cursor_image(icon, image, hotspot);
+            }
+            state.update_from_context(&egui_ctx);
+            state.fuse_output(win, egui_output.platform_output);
+            if state.is_scrolling() {
+                egui_ctx.request_repaint();
+            }
+            if let Some(image) = frame.copied_image.take() {
+                state.copy_image(&image);
+            }
+            if std::mem::take(&mut frame.image_paste_requested) {
+                state.request_image_paste(win);
+                // Synchronous clipboards hand over the image right away.
+                egui_ctx.request_repaint();
+            }
+            let meshes = egui_ctx.tessellate(egui_output.shapes, egui_output.pixels_per_point);
+            painter.borrow_mut().paint_and_update_textures(
+                state.canvas_size,
+                egui_output.pixels_per_point,
+                &meshes,
+                &egui_output.textures_delta,
+            );
+
+            ViewportManager::handle_output(&viewports, egui_output.viewport_output, true);
+            let commands = viewports.borrow_mut().take_root_commands();
+            state.fuse_viewport_commands(win, commands);
+            if state.close_requested() {
+                frame.close();
+            }
+        }
+    });
+
+    while fltk_app.wait() {
+        if close_requested.get() {
+            break;
+        }
+    }
+
+    viewports.borrow_mut().close_all();
+    win.make_current();
+    let Runner { egui_app, frame } = &mut *runner.borrow_mut();
+    if let Some(storage) = frame.storage.as_deref_mut() {
+        storage.set_string(ZOOM_FACTOR_KEY, state.borrow().visual_scale().to_string());
+        egui_app.save(storage);
+        storage.flush();
+    }
+    let mut painter = painter.borrow_mut();
+    egui_app.on_exit(Some(painter.gl()));
+    painter.destroy();
+    win.hide();
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Instant,
+};
+
+use egui::{ViewportBuilder, ViewportId, ViewportIdMap};
+use egui_glow::Painter;
+use fltk::{
+    enums,
+    prelude::{WidgetBase, WidgetExt, WindowExt},
+    window::GlWindow,
+};
+
+use crate::{multi_viewport::ViewportManager, EguiState, RepaintScheduler};
+
+type UiCallback = Box<dyn FnMut(&egui::Context)>;
+
+/// A window showing the shared context, with its own input state.
+struct SharedWindow {
+    win: GlWindow,
+    state: Rc<RefCell<EguiState>>,
+    ui: Rc<RefCell<UiCallback>>,
+}
+
+struct Shared {
+    ctx: egui::Context,
+    /// The window whose GL context all the windows draw with.
+    root: GlWindow,
+    painter: Rc<RefCell<Painter>>,
+    scheduler: RepaintScheduler,
+    windows: RefCell<ViewportIdMap<SharedWindow>>,
+    /// Opens the viewports the app shows with `show_viewport_deferred` and `_immediate`.
+    viewports: Rc<RefCell<ViewportManager>>,
+    start_time: Instant,
+    /// Set by [`SharedContext::destroy`], the windows don't draw anymore.
+    destroyed: Cell<bool>,
+}
+
+/// One egui context shown in several `GlWindow`s, e.g. docked panels of the same app.
+///
+/// Each window is a viewport of the context with its own [`EguiState`]. They all draw
+/// with the GL context of the first window, so a single [`Painter`] uploads the textures
+/// once, including those of [`crate::RetainedEguiImage`]:
+/// ```rust,no_run
+/// use fltk::{prelude::*, *};
+///
+/// let app = app::App::default();
+/// let mut win = window::Window::default().with_size(800, 600);
+/// let mut canvas = window::GlWindow::new(5, 5, 590, 590, None);
+/// canvas.set_mode(enums::Mode::Opengl3);
+/// canvas.end();
+/// let mut inspector = window::GlWindow::new(600, 5, 195, 590, None);
+/// inspector.set_mode(enums::Mode::Opengl3);
+/// inspector.end();
+/// win.end();
+/// win.show();
+///
+/// let shared = fltk_egui::SharedContext::new(&mut canvas, |ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| ui.label("Canvas"));
+/// });
+/// shared.add_window(&mut inspector, |ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| ui.label("Inspector"));
+/// });
+/// win.set_callback(move |win| {
+///     shared.destroy();
+///     win.hide();
+/// });
+/// app.run().unwrap();
+/// ```
+///
+/// The windows get their own `handle` and `draw` callbacks, so don't override them.
+/// Call [`SharedContext::destroy`] before the root window closes.
+#[derive(Clone)]
+pub struct SharedContext {
+    shared: Rc<Shared>,
+}
+
+impl SharedContext {
+    /// Show a new context in `win`, which must be shown already, as the root viewport.
+    ///
+    /// The other windows are registered as deferred viewports during the root frames,
+    /// so this turns off embedded viewports. The viewports the UIs show themselves get
+    /// windows of their own.
+    pub fn new<F: FnMut(&egui::Context) + 'static>(win: &mut GlWindow, ui: F) -> Self {
+        win.make_current();
+        let (painter, state) = crate::init(win);
+        let ctx = egui::Context::default();
+        let scheduler = RepaintScheduler::new(&ctx, win);
+        let painter = Rc::new(RefCell::new(painter));
+        let start_time = Instant::now();
+        let viewports =
+            ViewportManager::new(&ctx, win, painter.clone(), scheduler.clone(), start_time);
+        let shared = Self {
+            shared: Rc::new(Shared {
+                ctx,
+                root: win.clone(),
+                painter,
+                scheduler,
+                windows: RefCell::default(),
+                viewports,
+                start_time,
+                destroyed: Cell::new(false),
+            }),
+        };
+        shared.attach(win, ViewportId::ROOT, state, ui);
+        shared
+    }
+
+    /// Show the context in `win` too, with its own UI.
+    ///
+    /// Returns the viewport of the window, which `ctx.viewport_id()` returns in `ui`.
+    pub fn add_window<F: FnMut(&egui::Context) + 'static>(
+        &self,
+        win: &mut GlWindow,
+        ui: F,
+    ) -> ViewportId {
+        let viewport_id =
+            ViewportId::from_hash_of(("fltk_egui_shared", self.shared.windows.borrow().len()));
+        self.share_gl_context(win);
+        let mut state = EguiState::new(win, self.shared.painter.borrow().max_texture_side());
+        state.viewport_id = viewport_id;
+        self.shared.scheduler.add_viewport(viewport_id, win);
+        self.shared
+            .viewports
+            .borrow_mut()
+            .add_external(viewport_id, win);
+        self.attach(win, viewport_id, state, ui);
+        // The root frame registers the viewport.
+        self.shared.ctx.request_repaint_of(ViewportId::ROOT);
+        viewport_id
+    }
+
+    /// Make `win` draw with the GL context of the root window, for GL drawing of your own
+    /// with the textures of the shared painter.
+    pub fn share_gl_context(&self, win: &mut GlWindow) {
+        if let Some(context) = self.shared.root.context() {
+            // FLTK keeps the context alive when `win` is hidden.
+            win.set_context(context, false);
+        }
+    }
+
+    /// The shared egui context.
+    pub fn context(&self) -> &egui::Context {
+        &self.shared.ctx
+    }
+
+    /// The painter all the windows draw with.
+    pub fn painter(&self) -> Rc<RefCell<Painter>> {
+        self.shared.painter.clone()
+    }
+
+    /// Free the painter's GL resources and close the windows of the app's viewports,
+    /// e.g. from the callback of the top-level window.
+    ///
+    /// FLTK deletes the GL context when the root window is hidden, without telling the
+    /// windows first. They don't draw anymore afterwards.
+    pub fn destroy(&self) {
+        if self.shared.destroyed.replace(true) {
+            return;
+        }
+        self.shared.viewports.borrow_mut().close_all();
+        let mut root = self.shared.root.clone();
+        if root.shown() {
+            root.make_current();
+            self.shared.painter.borrow_mut().destroy();
+        }
+    }
+
+    fn attach<F: FnMut(&egui::Context) + 'static>(
+        &self,
+        win: &mut GlWindow,
+        viewport_id: ViewportId,
+        state: EguiState,
+        ui: F,
+    ) {
+        let state = Rc::new(RefCell::new(state));
+        self.shared.windows.borrow_mut().insert(
+            viewport_id,
+            SharedWindow {
+                win: win.clone(),
+                state: state.clone(),
+                ui: Rc::new(RefCell::new(Box::new(ui))),
+            },
+        );
+
+        win.handle(move |win, ev| match ev {
+            ev if crate::is_fused_event(ev) => {
+                let Ok(mut state) = state.try_borrow_mut() else {
+                    return false;
+                };
+                let consumed = state.fuse_input(win, ev);
+                if consumed && ev == enums::Event::Push {
+                    // Keyboard events only reach the focused widget.
+                    win.take_focus().ok();
+                }
+                win.redraw();
+                consumed
+            }
+            _ => false,
+        });
+
+        win.draw({
+            let shared = Rc::downgrade(&self.shared);
+            move |win| {
+                if let Some(shared) = shared.upgrade() {
+                    shared.draw(viewport_id, win);
+                }
+            }
+        });
+        win.redraw();
+    }
+}
+
+impl Shared {
+    fn draw(&self, viewport_id: ViewportId, win: &mut GlWindow) {
+        if self.destroyed.get() {
+            return;
+        }
+        let Some((state, ui)) = self
+            .windows
+            .borrow()
+            .get(&viewport_id)
+            .map(|window| (window.state.clone(), window.ui.clone()))
+        else {
+            return;
+        };
+        let others: Vec<ViewportId> = if viewport_id == ViewportId::ROOT {
+            self.windows
+                .borrow()
+                .keys()
+                .filter(|&&id| id != ViewportId::ROOT)
+                .copied()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut state = state.borrow_mut();
+        // Catches scale changes of the screen that came without a resize.
+        state.fuse_window_scale(win);
+        state.fuse_viewport_info(win);
+        self.painter.borrow_mut().clear(
+            state.canvas_size,
+            self.ctx
+                .style()
+                .visuals
+                .panel_fill
+                .to_normalized_gamma_f32(),
+        );
+        state.input.time = Some(self.start_time.elapsed().as_secs_f64());
+        let egui_output = self.ctx.run(state.take_input(), |ctx| {
+            (ui.borrow_mut())(ctx);
+            state.update_from_viewport(ctx);
+            // egui forgets the viewports the root frame doesn't show.
+            for &id in &others {
+                ctx.show_viewport_deferred(id, ViewportBuilder::default(), |_, _| {});
+            }
+        });
+
+        state.fuse_output(win, egui_output.platform_output);
+        state.update_from_context(&self.ctx);
+        if state.is_scrolling() {
+            self.ctx.request_repaint_of(viewport_id);
+        }
+        let meshes = self
+            .ctx
+            .tessellate(egui_output.shapes, egui_output.pixels_per_point);
+        self.painter.borrow_mut().paint_and_update_textures(
+            state.canvas_size,
+            egui_output.pixels_per_point,
+            &meshes,
+            &egui_output.textures_delta,
+        );
+        drop(state);
+
+        let mut app_viewports = ViewportIdMap::default();
+        for (id, output) in egui_output.viewport_output {
+            let window = self
+                .windows
+                .borrow()
+                .get(&id)
+                .map(|window| (window.win.clone(), window.state.clone()));
+            let Some((win, state)) = window else {
+                app_viewports.insert(id, output);
+                continue;
+            };
+            self.apply_commands(win, &state, output.commands);
+            self.scheduler.schedule_viewport(id, output.repaint_delay);
+        }
+        ViewportManager::handle_output(
+            &self.viewports,
+            app_viewports,
+            viewport_id == ViewportId::ROOT,
+        );
+        // Sent to the root viewport from the frames of the app's viewports.
+        let commands = self.viewports.borrow_mut().take_root_commands();
+        let root = self
+            .windows
+            .borrow()
+            .get(&ViewportId::ROOT)
+            .map(|window| (window.win.clone(), window.state.clone()));
+        if let Some((win, state)) = root {
+            self.apply_commands(win, &state, commands);
+        }
+    }
+
+    fn apply_commands(
+        &self,
+        mut win: GlWindow,
+        state: &RefCell<EguiState>,
+        commands: Vec<egui::ViewportCommand>,
+    ) {
+        if commands.is_empty() {
+            return;
+        }
+        if let Ok(mut state) = state.try_borrow_mut() {
+            state.fuse_viewport_commands(&mut win, commands);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+/// A key-value store apps can persist their state in.
+pub trait Storage {
+    /// Get the value stored under `key`.
+    fn get_string(&self, key: &str) -> Option<String>;
+
+    /// Store `value` under `key`.
+    fn set_string(&mut self, key: &str, value: String);
+
+    /// Write the stored values to disk.
+    fn flush(&mut self);
+}
+
+/// A [`Storage`] backed by a plain text file, one `key=value` per line.
+pub struct FileStorage {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+    dirty: bool,
+}
+
+impl FileStorage {
+    /// Load the values stored at `path`, starting empty if it can't be read.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let values = fs::read_to_string(&path)
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (unescape(key), unescape(value)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            path,
+            values,
+            dirty: false,
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.values.get(key) != Some(&value) {
+            self.values.insert(key.to_owned(), value);
+            self.dirty = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(dir) = self.path.parent() {
+            if let Err(err) = fs::create_dir_all(dir) {
+                eprintln!("Failed to create storage directory: {}", err);
+                return;
+            }
+        }
+        let mut contents = String::new();
+        for (key, value) in &self.values {
+            contents.push_str(&escape(key));
+            contents.push('=');
+            contents.push_str(&escape(value));
+            contents.push('\n');
+        }
+        match fs::write(&self.path, contents) {
+            Ok(()) => self.dirty = false,
+            Err(err) => eprintln!("Failed to save storage: {}", err),
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '=' => escaped.push_str("\\e"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('e') => unescaped.push('='),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
@@ -0,0 +1,46 @@
+use egui::{pos2, Event, TouchDeviceId, TouchId, TouchPhase};
+
+/// The device id used for pen contacts.
+pub const PEN_DEVICE: TouchDeviceId = TouchDeviceId(0);
+
+/// A pen or finger contact with the window.
+///
+/// These don't come from FLTK: fltk-rs 1.5.23 has no binding of FLTK's pen API (`Fl::Pen`),
+/// and FLTK's events report touches as plain mouse events. Apps reading a tablet or touch
+/// screen by other means pass the samples to [`crate::EguiState::fuse_touch`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchPoint {
+    pub device_id: TouchDeviceId,
+    /// Identifies the contact while it lasts, e.g. the pen or the finger.
+    pub id: u64,
+    pub phase: TouchPhase,
+    /// Position in window units, like `app::event_coords()`.
+    pub x: f32,
+    pub y: f32,
+    /// Pressure from 0.0 to 1.0, when the device reports it.
+    pub force: Option<f32>,
+}
+
+impl TouchPoint {
+    /// A pen sample at `(x, y)` with the given pressure.
+    pub fn pen(phase: TouchPhase, x: f32, y: f32, pressure: f32) -> Self {
+        Self {
+            device_id: PEN_DEVICE,
+            id: 0,
+            phase,
+            x,
+            y,
+            force: Some(pressure),
+        }
+    }
+
+    pub(crate) fn to_egui(self, visual_scale: f32) -> Event {
+        Event::Touch {
+            device_id: self.device_id,
+            id: TouchId(self.id),
+            phase: self.phase,
+            pos: pos2(self.x / visual_scale, self.y / visual_scale),
+            force: self.force,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use egui::{pos2, vec2, Event, Rect, ViewportCommand, ViewportEvent, ViewportInfo, WindowLevel};
+use fltk::{
+    app, enums,
+    image::RgbImage,
+    prelude::{GroupExt, WidgetExt, WindowExt},
+    window::GlWindow,
+};
+
+use crate::EguiState;
+
+impl EguiState {
+    /// Apply the commands egui sent for this window's viewport,
+    /// from `FullOutput::viewport_output`.
+    ///
+    /// `Close` is only recorded, see [`EguiState::close_requested`]. `Visible(false)` is
+    /// ignored, hiding a `GlWindow` deletes its GL context. FLTK can only put a window
+    /// on top, so `WindowLevel::Normal` and `AlwaysOnBottom` are ignored too.
+    pub fn fuse_viewport_commands(&mut self, win: &mut GlWindow, commands: Vec<ViewportCommand>) {
+        for command in commands {
+            match command {
+                ViewportCommand::Close => self.close_requested = true,
+                ViewportCommand::CancelClose => self.close_requested = false,
+                ViewportCommand::Title(title) => win.set_label(&title),
+                ViewportCommand::OuterPosition(pos) => {
+                    // FLTK places the inner rect.
+                    let (border, title_bar) = decorations(win);
+                    win.set_pos(
+                        self.to_units(pos.x) + border,
+                        self.to_units(pos.y) + title_bar,
+                    )
+                }
+                ViewportCommand::InnerSize(size) => {
+                    win.set_size(self.to_units(size.x), self.to_units(size.y))
+                }
+                ViewportCommand::MinInnerSize(size) => {
+                    self.min_inner_size = size;
+                    self.fuse_size_range(win);
+                }
+                ViewportCommand::MaxInnerSize(size) => {
+                    self.max_inner_size = size;
+                    self.fuse_size_range(win);
+                }
+                ViewportCommand::Resizable(resizable) => win.make_resizable(resizable),
+                ViewportCommand::Minimized(true) => win.iconize(),
+                ViewportCommand::Minimized(false)
+                | ViewportCommand::Focus
+                | ViewportCommand::Visible(true) => win.show(),
+                ViewportCommand::Maximized(true) => win.maximize(),
+                ViewportCommand::Maximized(false) => win.un_maximize(),
+                ViewportCommand::Fullscreen(fullscreen) => win.fullscreen(fullscreen),
+                ViewportCommand::Decorations(decorated) => win.set_border(decorated),
+                ViewportCommand::Icon(Some(icon)) => {
+                    // A malformed icon is dropped, the window keeps its icon.
+                    if let Ok(image) = RgbImage::new(
+                        &icon.rgba,
+                        icon.width as i32,
+                        icon.height as i32,
+                        enums::ColorDepth::Rgba8,
+                    ) {
+                        win.set_icon(Some(image));
+                    }
+                }
+                ViewportCommand::Icon(None) => win.set_icon(None::<RgbImage>),
+                ViewportCommand::WindowLevel(WindowLevel::AlwaysOnTop) if win.shown() => {
+                    win.set_on_top()
+                }
+                ViewportCommand::RequestCopy => {
+                    self.input.events.push(Event::Copy);
+                }
+                ViewportCommand::RequestCut => {
+                    self.input.events.push(Event::Cut);
+                }
+                ViewportCommand::RequestPaste => {
+                    if let Some(text) = self.clipboard.request_text(win) {
+                        self.input.events.push(Event::Paste(text));
+                    }
+                }
+                // No FLTK counterpart.
+                _ => {}
+            }
+        }
+        self.fuse_viewport_info(win);
+    }
+
+    /// Whether the window should close: egui sent `ViewportCommand::Close`, or the user
+    /// closed the window (see [`EguiState::request_close`]) and egui didn't cancel it.
+    pub fn close_requested(&self) -> bool {
+        self.close_requested
+    }
+
+    /// Tell egui the user wants to close the window, call this from the window's callback.
+    ///
+    /// egui can cancel it with `ViewportCommand::CancelClose` in the next frame.
+    pub fn request_close(&mut self) {
+        self.viewport_info().events.push(ViewportEvent::Close);
+        self.close_requested = true;
+    }
+
+    /// Report the window state in egui's `ViewportInfo`, call this before each frame.
+    pub fn fuse_viewport_info(&mut self, win: &GlWindow) {
+        let focused = self.input.focused;
+        let to_points = |units: i32| units as f32 / self.visual_scale;
+
+        let inner_rect = Rect::from_min_size(
+            pos2(to_points(win.x_root()), to_points(win.y_root())),
+            vec2(to_points(win.w()), to_points(win.h())),
+        );
+        let (border, title_bar) = decorations(win);
+        let outer_rect = Rect::from_min_size(
+            pos2(
+                to_points(win.x_root() - border),
+                to_points(win.y_root() - title_bar),
+            ),
+            vec2(to_points(win.decorated_w()), to_points(win.decorated_h())),
+        );
+        let (_, _, screen_w, screen_h) = app::screen_xywh(win.screen_num());
+        let monitor_size = vec2(to_points(screen_w), to_points(screen_h));
+
+        let info = self.viewport_info();
+        info.inner_rect = Some(inner_rect);
+        info.outer_rect = Some(outer_rect);
+        info.monitor_size = Some(monitor_size);
+        info.title = Some(win.label());
+        info.minimized = Some(!win.visible_r());
+        info.maximized = Some(win.maximize_active());
+        info.fullscreen = Some(win.fullscreen_active());
+        info.focused = Some(focused);
+    }
+
+    /// The info egui gets about this window's viewport.
+    pub(crate) fn viewport_info(&mut self) -> &mut ViewportInfo {
+        self.input.viewports.entry(self.viewport_id).or_default()
+    }
+
+    fn fuse_size_range(&self, win: &mut GlWindow) {
+        // FLTK takes 0 for no maximum.
+        let max = |points: f32| {
+            if points.is_finite() {
+                self.to_units(points)
+            } else {
+                0
+            }
+        };
+        win.size_range(
+            self.to_units(self.min_inner_size.x),
+            self.to_units(self.min_inner_size.y),
+            max(self.max_inner_size.x),
+            max(self.max_inner_size.y),
+        );
+    }
+
+    /// Points to FLTK units.
+    fn to_units(&self, points: f32) -> i32 {
+        (points * self.visual_scale).round() as i32
+    }
+}
+
+/// The left border and title bar sizes of the window, in FLTK units.
+///
+/// FLTK only knows the decorated size, assume equal borders left, right and bottom.
+fn decorations(win: &GlWindow) -> (i32, i32) {
+    let border = (win.decorated_w() - win.w()) / 2;
+    let title_bar = win.decorated_h() - win.h() - border;
+    (border, title_bar)
+}
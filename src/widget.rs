@@ -0,0 +1,167 @@
+use std::{cell::RefCell, rc::Rc, time::Instant};
+
+use egui_glow::Painter;
+use fltk::{
+    enums,
+    prelude::{GroupExt, WidgetBase, WidgetExt, WindowExt},
+    window::GlWindow,
+};
+
+use crate::{EguiState, RepaintScheduler};
+
+type UiCallback = Box<dyn FnMut(&egui::Context)>;
+
+/// The GL painter and input state, created on the first draw once a GL context exists.
+struct Backend {
+    painter: Painter,
+    state: EguiState,
+}
+
+/// An FLTK widget hosting its own egui context.
+///
+/// It derefs to the [`GlWindow`] it wraps and can be placed in any group like other widgets:
+/// ```rust,no_run
+/// use fltk::{prelude::*, *};
+///
+/// let app = app::App::default();
+/// let mut win = window::Window::default().with_size(400, 300);
+/// let mut egui_widget = fltk_egui::EguiWidget::new(5, 5, 390, 290);
+/// egui_widget.set_ui(|ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| ui.label("Hello from egui!"));
+/// });
+/// win.end();
+/// win.show();
+/// app.run().unwrap();
+/// ```
+///
+/// The widget installs its own `handle` and `draw` callbacks, so don't override them.
+/// Call [`EguiWidget::destroy`] before its window closes.
+#[derive(Clone)]
+pub struct EguiWidget {
+    win: GlWindow,
+    ctx: egui::Context,
+    ui: Rc<RefCell<Option<UiCallback>>>,
+    backend: Rc<RefCell<Option<Backend>>>,
+}
+
+fltk::widget_extends!(EguiWidget, GlWindow, win);
+
+impl Default for EguiWidget {
+    fn default() -> Self {
+        Self::new(0, 0, 0, 0)
+    }
+}
+
+impl EguiWidget {
+    /// Construct a new egui widget at the given position and size.
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut win = GlWindow::new(x, y, w, h, None);
+        win.set_mode(enums::Mode::Opengl3);
+        win.end();
+
+        let ctx = egui::Context::default();
+        let ui: Rc<RefCell<Option<UiCallback>>> = Rc::default();
+        let backend: Rc<RefCell<Option<Backend>>> = Rc::default();
+        let scheduler = RepaintScheduler::new(&ctx, &win);
+
+        win.handle({
+            let backend = backend.clone();
+            move |win, ev| match ev {
+                ev if crate::is_fused_event(ev) => {
+                    let Ok(mut backend) = backend.try_borrow_mut() else {
+                        return false;
+                    };
+                    let Some(backend) = backend.as_mut() else {
+                        return false;
+                    };
+                    let consumed = backend.state.fuse_input(win, ev);
+                    if consumed && ev == enums::Event::Push {
+                        // Keyboard events only reach the focused widget.
+                        win.take_focus().ok();
+                    }
+                    win.redraw();
+                    consumed
+                }
+                _ => false,
+            }
+        });
+
+        win.draw({
+            let backend = backend.clone();
+            let ctx = ctx.clone();
+            let ui = ui.clone();
+            let start_time = Instant::now();
+            move |win| {
+                let mut backend = backend.borrow_mut();
+                let Backend { painter, state } = backend.get_or_insert_with(|| {
+                    let (painter, state) = crate::init(win);
+                    Backend { painter, state }
+                });
+
+                // Catches scale changes of the screen that came without a resize.
+                state.fuse_window_scale(win);
+                state.fuse_viewport_info(win);
+                painter.clear(
+                    state.canvas_size,
+                    ctx.style().visuals.panel_fill.to_normalized_gamma_f32(),
+                );
+                state.input.time = Some(start_time.elapsed().as_secs_f64());
+                let egui_output = ctx.run(state.take_input(), |ctx| {
+                    if let Some(ui) = ui.borrow_mut().as_mut() {
+                        ui(ctx);
+                    }
+                });
+
+                state.update_from_context(&ctx);
+                state.fuse_output(win, egui_output.platform_output);
+                if state.is_scrolling() {
+                    ctx.request_repaint();
+                }
+                let meshes = ctx.tessellate(egui_output.shapes, egui_output.pixels_per_point);
+                painter.paint_and_update_textures(
+                    state.canvas_size,
+                    egui_output.pixels_per_point,
+                    &meshes,
+                    &egui_output.textures_delta,
+                );
+
+                if let Some(viewport_output) =
+                    egui_output.viewport_output.get(&egui::ViewportId::ROOT)
+                {
+                    scheduler.schedule(viewport_output.repaint_delay);
+                }
+            }
+        });
+
+        Self {
+            win,
+            ctx,
+            ui,
+            backend,
+        }
+    }
+
+    /// Set the closure building the egui UI, called each time the widget is drawn.
+    pub fn set_ui<F: FnMut(&egui::Context) + 'static>(&mut self, ui: F) {
+        *self.ui.borrow_mut() = Some(Box::new(ui));
+        self.win.redraw();
+    }
+
+    /// The egui context of this widget.
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// Free the painter's GL resources, e.g. from the callback of the top-level window.
+    ///
+    /// FLTK deletes the GL context when the window is hidden, without telling the widget first.
+    /// The widget sets up a new painter if it is drawn again.
+    pub fn destroy(&mut self) {
+        if let Some(mut backend) = self.backend.borrow_mut().take() {
+            if self.win.shown() {
+                self.win.make_current();
+                backend.painter.destroy();
+            }
+        }
+    }
+}